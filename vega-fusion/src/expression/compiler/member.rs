@@ -4,7 +4,7 @@ use crate::expression::ast::identifier::Identifier;
 use crate::expression::ast::member::MemberExpression;
 use crate::expression::compiler::compile;
 use crate::expression::compiler::config::CompilationConfig;
-use crate::expression::compiler::utils::{data_type, is_numeric_datatype, ExprHelpers};
+use crate::expression::compiler::utils::{data_type, is_numeric_datatype, simplify_expr, ExprHelpers};
 use datafusion::arrow::array::{
     new_null_array, Array, ArrayRef, Int32Array, Int64Array, ListArray, StructArray,
 };
@@ -70,13 +70,21 @@ pub fn compile_member(
     let compiled_object = compile(&node.object, config, Some(schema))?;
     let dtype = data_type(&compiled_object, schema)?;
 
-    let udf = match dtype {
-        DataType::Struct(_) => make_get_object_member_udf(&dtype, &property_string)?,
+    let member_expr = match dtype {
+        DataType::Struct(_) => {
+            let udf = make_get_object_member_udf(&dtype, &property_string)?;
+            Expr::ScalarUDF {
+                fun: Arc::new(udf),
+                args: vec![compiled_object],
+            }
+        }
         _ => {
             if property_string == "length" {
                 // Special case to treat foo.length as length(foo) when foo is not an object
-                // make_length()
-                todo!("Special case to treat foo.length as length(foo) when foo is not an object")
+                Expr::ScalarUDF {
+                    fun: Arc::new(make_length_udf()),
+                    args: vec![compiled_object],
+                }
             } else if matches!(
                 dtype,
                 DataType::List(_)
@@ -85,7 +93,18 @@ pub fn compile_member(
                     | DataType::LargeUtf8
             ) {
                 if let Some(index) = index {
-                    make_get_element_udf(index as i32)
+                    // Index is passed as a literal argument (rather than baked into the UDF's
+                    // name/closure like `make_get_object_member_udf` above) since the return
+                    // type here doesn't depend on which index is requested, only on `dtype` -
+                    // that lets a single `get` UDF be registered once under a fixed name, the
+                    // same way `length` is.
+                    Expr::ScalarUDF {
+                        fun: Arc::new(make_get_element_udf()),
+                        args: vec![
+                            compiled_object,
+                            Expr::Literal(ScalarValue::Int64(Some(index as i64))),
+                        ],
+                    }
                 } else {
                     return Err(VegaFusionError::compilation_error(&format!(
                         "Non-numeric element index: {}",
@@ -100,11 +119,7 @@ pub fn compile_member(
             }
         }
     };
-
-    Ok(Expr::ScalarUDF {
-        fun: Arc::new(udf),
-        args: vec![compiled_object],
-    })
+    simplify_expr(member_expr, schema)
 }
 
 pub fn make_get_object_member_udf(
@@ -151,9 +166,105 @@ pub fn make_get_object_member_udf(
     ))
 }
 
-pub fn make_get_element_udf(index: i32) -> ScalarUDF {
+/// Count of UTF-16 code units in `s`, matching JavaScript's `String.prototype.length` (not the
+/// byte length, and not the count of Unicode scalar values - characters outside the Basic
+/// Multilingual Plane are represented as a surrogate pair and so count as 2).
+fn utf16_length(s: &str) -> i32 {
+    s.chars()
+        .map(|c| if (c as u32) > 0xFFFF { 2 } else { 1 })
+        .sum()
+}
+
+/// `foo.length` when `foo` is not a struct: string length (in UTF-16 code units, matching Vega's
+/// JS runtime) for `Utf8`/`LargeUtf8`, and element count for `List`/`FixedSizeList`.
+pub fn make_length_udf() -> ScalarUDF {
+    let length_fn: ScalarFunctionImplementation = Arc::new(move |args: &[ColumnarValue]| {
+        let arg = &args[0];
+        Ok(match arg {
+            ColumnarValue::Scalar(value) => ColumnarValue::Scalar(match value {
+                ScalarValue::Utf8(Some(s)) | ScalarValue::LargeUtf8(Some(s)) => {
+                    ScalarValue::Int32(Some(utf16_length(s)))
+                }
+                ScalarValue::List(Some(arr), _) => ScalarValue::Int32(Some(arr.len() as i32)),
+                _ => ScalarValue::Int32(None),
+            }),
+            ColumnarValue::Array(array) => {
+                let result: Int32Array = match array.data_type() {
+                    DataType::Utf8 | DataType::LargeUtf8 => {
+                        let utf8 = cast(array, &DataType::Utf8).map_err(DataFusionError::ArrowError)?;
+                        let strings = utf8
+                            .as_any()
+                            .downcast_ref::<datafusion::arrow::array::StringArray>()
+                            .unwrap();
+                        (0..strings.len())
+                            .map(|i| {
+                                if strings.is_null(i) {
+                                    None
+                                } else {
+                                    Some(utf16_length(strings.value(i)))
+                                }
+                            })
+                            .collect()
+                    }
+                    DataType::List(_) => {
+                        let list_array = array.as_any().downcast_ref::<ListArray>().unwrap();
+                        let offsets = list_array.value_offsets();
+                        (0..list_array.len())
+                            .map(|i| {
+                                if list_array.is_null(i) {
+                                    None
+                                } else {
+                                    Some(offsets[i + 1] - offsets[i])
+                                }
+                            })
+                            .collect()
+                    }
+                    DataType::FixedSizeList(_, size) => {
+                        let size = *size;
+                        (0..array.len())
+                            .map(|i| if array.is_null(i) { None } else { Some(size) })
+                            .collect()
+                    }
+                    dtype => {
+                        return Err(DataFusionError::Internal(format!(
+                            "Unsupported datatype for length: {:?}",
+                            dtype
+                        )))
+                    }
+                };
+                ColumnarValue::Array(Arc::new(result))
+            }
+        })
+    });
+
+    let return_type: ReturnTypeFunction = Arc::new(|_| Ok(Arc::new(DataType::Int32)));
+
+    ScalarUDF::new(
+        "length",
+        &Signature::Any(1),
+        &return_type,
+        &length_fn,
+    )
+}
+
+/// `get(value, index)`: element access into a `List`, or the one-byte substring of a `Utf8`
+/// string at byte offset `index` (one character for ASCII text). Other input types, including
+/// `FixedSizeList` and `LargeUtf8`, are rejected when the return type is resolved. Takes `index` as an argument (rather than
+/// baking it into the UDF, one instance per call site, the way [`make_get_object_member_udf`]
+/// does for struct field names) since the return type here only depends on `value`'s data type,
+/// so a single instance can be registered once under the fixed name `get`.
+pub fn make_get_element_udf() -> ScalarUDF {
     let get_fn: ScalarFunctionImplementation = Arc::new(move |args: &[ColumnarValue]| {
-        // Signature ensures there is a single argument
+        let index = match &args[1] {
+            ColumnarValue::Scalar(ScalarValue::Int64(Some(i))) => *i as i32,
+            ColumnarValue::Scalar(ScalarValue::Int32(Some(i))) => *i,
+            other => {
+                return Err(DataFusionError::Internal(format!(
+                    "get index argument must be a scalar integer, found {:?}",
+                    other
+                )))
+            }
+        };
         let arg = &args[0];
         Ok(match arg {
             ColumnarValue::Scalar(value) => {
@@ -244,10 +355,5 @@ pub fn make_get_element_udf(index: i32) -> ScalarUDF {
         };
         Ok(Arc::new(new_dtype))
     });
-    ScalarUDF::new(
-        &format!("get[{}]", index),
-        &Signature::Any(1),
-        &return_type,
-        &get_fn,
-    )
+    ScalarUDF::new("get", &Signature::Any(2), &return_type, &get_fn)
 }
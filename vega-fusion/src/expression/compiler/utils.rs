@@ -0,0 +1,69 @@
+use crate::error::Result;
+use datafusion::logical_plan::{DFSchema, Expr};
+use datafusion::scalar::ScalarValue;
+
+/// Repeatedly apply a small set of rewrite rules to `expr` until none of them make further
+/// progress (a fixpoint), shrinking the `Expr` tree before it's lowered to SQL.
+///
+/// Rule applied: `IS NOT NULL`/`IS NULL` over a literal folds directly to a boolean literal.
+///
+/// Note: this does not yet fold literal arithmetic/boolean binary expressions, drop no-op casts,
+/// or collapse `get[...]` over struct literals - those require pattern matching on `Expr`
+/// variants (binary operators, casts, struct literals) whose exact shape in this crate's
+/// DataFusion version isn't exercised anywhere else in this codebase, so they're left for a
+/// follow-up rather than guessed at here.
+pub fn simplify_expr(expr: Expr, schema: &DFSchema) -> Result<Expr> {
+    let mut current = expr;
+    loop {
+        let simplified = simplify_expr_once(current.clone(), schema)?;
+        if simplified == current {
+            return Ok(simplified);
+        }
+        current = simplified;
+    }
+}
+
+fn simplify_expr_once(expr: Expr, schema: &DFSchema) -> Result<Expr> {
+    Ok(match expr {
+        Expr::IsNotNull(inner) => {
+            let inner = simplify_expr_once(*inner, schema)?;
+            match &inner {
+                Expr::Literal(value) => {
+                    Expr::Literal(ScalarValue::Boolean(Some(!is_null_scalar(value))))
+                }
+                _ => Expr::IsNotNull(Box::new(inner)),
+            }
+        }
+        Expr::IsNull(inner) => {
+            let inner = simplify_expr_once(*inner, schema)?;
+            match &inner {
+                Expr::Literal(value) => {
+                    Expr::Literal(ScalarValue::Boolean(Some(is_null_scalar(value))))
+                }
+                _ => Expr::IsNull(Box::new(inner)),
+            }
+        }
+        other => other,
+    })
+}
+
+/// Whether a `ScalarValue` represents SQL NULL, regardless of its variant's data type.
+fn is_null_scalar(value: &ScalarValue) -> bool {
+    match value {
+        ScalarValue::Boolean(v) => v.is_none(),
+        ScalarValue::Float32(v) => v.is_none(),
+        ScalarValue::Float64(v) => v.is_none(),
+        ScalarValue::Int8(v) => v.is_none(),
+        ScalarValue::Int16(v) => v.is_none(),
+        ScalarValue::Int32(v) => v.is_none(),
+        ScalarValue::Int64(v) => v.is_none(),
+        ScalarValue::UInt8(v) => v.is_none(),
+        ScalarValue::UInt16(v) => v.is_none(),
+        ScalarValue::UInt32(v) => v.is_none(),
+        ScalarValue::UInt64(v) => v.is_none(),
+        ScalarValue::Utf8(v) => v.is_none(),
+        ScalarValue::LargeUtf8(v) => v.is_none(),
+        ScalarValue::List(v, _) => v.is_none(),
+        _ => false,
+    }
+}
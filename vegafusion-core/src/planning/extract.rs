@@ -4,9 +4,10 @@ use crate::proto::gen::tasks::Variable;
 use crate::spec::chart::{ChartSpec, MutChartVisitor};
 use crate::spec::data::{DataSpec, DataSupported};
 use crate::spec::mark::MarkSpec;
+use crate::spec::transform::TransformSpec;
 use crate::task_graph::scope::TaskScope;
 use crate::task_graph::task_graph::ScopedVariable;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
 pub fn extract_server_data(
     client_spec: &mut ChartSpec,
@@ -40,28 +41,67 @@ impl<'a> ExtractServerDataVisitor<'a> {
     }
 }
 
+/// Partition a dataset's transforms into a server-evaluated and a client-evaluated list,
+/// preserving the relative order of each partition.
+///
+/// A transform is placed on the server only if it is itself supported and all of the
+/// fields/signals it reads (`input_vars`) are available server-side: either they're produced by
+/// an earlier transform that was itself placed on the server, or they aren't produced by any
+/// transform in this pipeline at all (in which case they come from the dataset's own source).
+/// This lets independent supported transforms run on the server even after an unsupported
+/// transform, as long as they don't depend on anything that transform (or its downstream
+/// transforms) produced.
+///
+/// This relies on `TransformSpec::output_vars()` - the columns a transform adds or renames, as
+/// opposed to `output_signals()` - in addition to `input_vars()`/`output_signals()`/`supported()`.
+/// `TransformSpec`'s defining module (`vegafusion_core::spec`) is not present in this checkout, so
+/// `output_vars()`'s existence and signature can't be confirmed to compile from here; it must be
+/// landed on `TransformSpec` (returning the transform's added/renamed field names, the same shape
+/// as `output_signals()`'s `Vec<String>`) before this function can be verified.
+fn partition_transforms_by_dependency(
+    transforms: &[TransformSpec],
+) -> (Vec<TransformSpec>, Vec<TransformSpec>) {
+    // Names produced anywhere in this pipeline, used to distinguish "external" input (from the
+    // dataset's source) from input that depends on another transform in this list.
+    let produced_by_any: HashSet<String> = transforms
+        .iter()
+        .flat_map(|tx| tx.output_vars().into_iter().chain(tx.output_signals()))
+        .collect();
+
+    let mut server_produced: HashSet<String> = HashSet::new();
+    let mut server_tx = Vec::new();
+    let mut client_tx = Vec::new();
+
+    for tx in transforms {
+        let inputs_available = tx
+            .input_vars()
+            .iter()
+            .all(|var| !produced_by_any.contains(var) || server_produced.contains(var));
+
+        if tx.supported() && inputs_available {
+            server_produced.extend(tx.output_vars());
+            server_produced.extend(tx.output_signals());
+            server_tx.push(tx.clone());
+        } else {
+            client_tx.push(tx.clone());
+        }
+    }
+
+    (server_tx, client_tx)
+}
+
 impl<'a> MutChartVisitor for ExtractServerDataVisitor<'a> {
     fn visit_data(&mut self, data: &mut DataSpec, scope: &[u32]) -> Result<()> {
         let data_var: ScopedVariable = (Variable::new_data(&data.name), Vec::from(scope));
 
         match self.supported_vars.get(&data_var) {
             Some(DataSupported::PartiallySupported) => {
-                // Split transforms at first unsupported transform.
-                // Note: There could be supported transforms in the client_tx after an unsupported
-                // transform.
-                let server_tx: Vec<_> = data
-                    .transform
-                    .iter()
-                    .cloned()
-                    .take_while(|tx| tx.supported())
-                    .collect();
-
-                let client_tx: Vec<_> = data
-                    .transform
-                    .iter()
-                    .cloned()
-                    .skip_while(|tx| tx.supported())
-                    .collect();
+                // Split transforms by dependency rather than position: a transform runs on the
+                // server if it's itself supported and every field/signal it reads is either
+                // produced on the server already or isn't produced by any transform in this
+                // pipeline (i.e. it comes from the dataset's source). Everything else -
+                // unsupported transforms and anything downstream of them - stays on the client.
+                let (server_tx, client_tx) = partition_transforms_by_dependency(&data.transform);
 
                 // Compute new name for server data
                 let mut server_name = data.name.clone();
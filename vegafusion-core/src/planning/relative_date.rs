@@ -0,0 +1,401 @@
+/*
+ * VegaFusion
+ * Copyright (C) 2022 VegaFusion Technologies LLC
+ *
+ * This program is distributed under multiple licenses.
+ * Please consult the license documentation provided alongside
+ * this program the details of the active license.
+ */
+use crate::error::{Result, VegaFusionError};
+use crate::spec::chart::{ChartSpec, MutChartVisitor};
+use crate::spec::data::DataSpec;
+use crate::spec::transform::filter::FilterTransformSpec;
+use crate::spec::transform::formula::FormulaTransformSpec;
+use crate::spec::transform::TransformSpec;
+use chrono::{DateTime, Datelike, Months, NaiveDateTime, TimeZone, Utc};
+use std::str::FromStr;
+
+/// This planning phase pre-resolves relative date expressions (e.g. `"last 7 days"`, `"this
+/// month"`, `"yesterday"`) embedded in filter/formula transform expressions into concrete
+/// `[start, end]` UTC-millisecond pairs, computed once at plan time against the chart's
+/// `local_tz` - see [`stringify_local_datetimes`](crate::planning::stringify_local_datetimes),
+/// which solves the analogous problem for *displaying* datetimes, for the reasoning behind
+/// threading a timezone through plan-time date handling rather than leaving it to the viewer's
+/// browser.
+///
+/// Resolving a relative expression server-side (rather than leaving it to be evaluated as a
+/// Vega expression at query/render time) lets VegaFusion push the resulting bounds down into the
+/// data query as literal bounds, and guarantees the server and client agree on the exact same
+/// window regardless of when either one is evaluated.
+///
+/// A relative date expression is written as a call to the pseudo-function
+/// `relativeDateRange('<phrase>')` inside a filter or formula `expr` string - see
+/// [`parse_relative_date`] for the supported phrases. `now_utc` is threaded in by the caller
+/// (rather than read from the system clock here) so that repeated calls across the server and
+/// client specs resolve to the exact same instant.
+pub fn resolve_relative_dates(
+    server_spec: &mut ChartSpec,
+    client_spec: &mut ChartSpec,
+    local_tz: &str,
+    now_utc: DateTime<Utc>,
+) -> Result<()> {
+    let mut visitor = ResolveRelativeDatesVisitor::new(local_tz, now_utc);
+    server_spec.walk_mut(&mut visitor)?;
+    client_spec.walk_mut(&mut visitor)?;
+    Ok(())
+}
+
+/// Calendar unit a relative date phrase counts in.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum RelativeDateUnit {
+    Day,
+    Week,
+    Month,
+    Quarter,
+    Year,
+}
+
+/// Where a relative date range is anchored.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum RelativeDateAnchor {
+    /// A rolling window of `count` units ending (for a negative count) or starting (for a
+    /// positive count) at `now`, e.g. "last 7 days" is the 7 calendar days up to now.
+    Now,
+    /// A window aligned to the boundaries of the unit containing `now + count` units, e.g.
+    /// "this month" is local midnight on the 1st through local midnight on the 1st of next
+    /// month, and "yesterday" is local midnight yesterday through local midnight today.
+    StartOf,
+    /// A window from `now` through the closing boundary of the unit containing `now + count`
+    /// units, e.g. "end of month" is now through local midnight on the 1st of next month, and
+    /// "end of last month" is now through local midnight on the 1st of this month.
+    EndOf,
+}
+
+/// A parsed relative date phrase: `count` signed units of `unit`, interpreted relative to `now`
+/// according to `anchor`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct RelativeDateExpr {
+    anchor: RelativeDateAnchor,
+    count: i64,
+    unit: RelativeDateUnit,
+}
+
+/// Parse one of the relative date phrases this phase understands:
+/// - `"yesterday"` / `"today"` / `"tomorrow"` - the calendar day before/containing/after `now`
+/// - `"this <unit>"` / `"next <unit>"` / `"last <unit>"` - the calendar `<unit>` containing,
+///   following, or preceding the one `now` falls in
+/// - `"last N <unit>s"` / `"next N <unit>s"` - a rolling `N`-unit window ending/starting at `now`
+/// - `"end of <unit>"` / `"end of next <unit>"` / `"end of last <unit>"` - `now` through the
+///   closing boundary of the calendar `<unit>` containing, following, or preceding the one `now`
+///   falls in
+///
+/// `<unit>` is one of `day`, `week`, `month`, `quarter`, `year` (singular or plural). Returns
+/// `None` if `phrase` doesn't match this grammar.
+fn parse_relative_date(phrase: &str) -> Option<RelativeDateExpr> {
+    let phrase = phrase.trim();
+    match phrase {
+        "yesterday" => {
+            return Some(RelativeDateExpr {
+                anchor: RelativeDateAnchor::StartOf,
+                count: -1,
+                unit: RelativeDateUnit::Day,
+            })
+        }
+        "today" => {
+            return Some(RelativeDateExpr {
+                anchor: RelativeDateAnchor::StartOf,
+                count: 0,
+                unit: RelativeDateUnit::Day,
+            })
+        }
+        "tomorrow" => {
+            return Some(RelativeDateExpr {
+                anchor: RelativeDateAnchor::StartOf,
+                count: 1,
+                unit: RelativeDateUnit::Day,
+            })
+        }
+        _ => {}
+    }
+
+    let words: Vec<&str> = phrase.split_whitespace().collect();
+    match words.as_slice() {
+        ["this", unit] => Some(RelativeDateExpr {
+            anchor: RelativeDateAnchor::StartOf,
+            count: 0,
+            unit: parse_unit(unit)?,
+        }),
+        ["last", unit] => Some(RelativeDateExpr {
+            anchor: RelativeDateAnchor::StartOf,
+            count: -1,
+            unit: parse_unit(unit)?,
+        }),
+        ["next", unit] => Some(RelativeDateExpr {
+            anchor: RelativeDateAnchor::StartOf,
+            count: 1,
+            unit: parse_unit(unit)?,
+        }),
+        ["last", count, unit] => Some(RelativeDateExpr {
+            anchor: RelativeDateAnchor::Now,
+            count: -count.parse::<i64>().ok()?,
+            unit: parse_unit(unit)?,
+        }),
+        ["next", count, unit] => Some(RelativeDateExpr {
+            anchor: RelativeDateAnchor::Now,
+            count: count.parse::<i64>().ok()?,
+            unit: parse_unit(unit)?,
+        }),
+        ["end", "of", unit] => Some(RelativeDateExpr {
+            anchor: RelativeDateAnchor::EndOf,
+            count: 0,
+            unit: parse_unit(unit)?,
+        }),
+        ["end", "of", "last", unit] => Some(RelativeDateExpr {
+            anchor: RelativeDateAnchor::EndOf,
+            count: -1,
+            unit: parse_unit(unit)?,
+        }),
+        ["end", "of", "next", unit] => Some(RelativeDateExpr {
+            anchor: RelativeDateAnchor::EndOf,
+            count: 1,
+            unit: parse_unit(unit)?,
+        }),
+        _ => None,
+    }
+}
+
+fn parse_unit(word: &str) -> Option<RelativeDateUnit> {
+    match word.trim_end_matches('s') {
+        "day" => Some(RelativeDateUnit::Day),
+        "week" => Some(RelativeDateUnit::Week),
+        "month" => Some(RelativeDateUnit::Month),
+        "quarter" => Some(RelativeDateUnit::Quarter),
+        "year" => Some(RelativeDateUnit::Year),
+        _ => None,
+    }
+}
+
+/// Add `count` units of `unit` to `dt`, using calendar-aware arithmetic (not a fixed millisecond
+/// width) so that e.g. adding a month to January 31st lands on a valid day in February rather
+/// than overflowing, and so DST transitions are handled by the timezone conversion that happens
+/// separately, not baked into this step.
+fn add_units(dt: NaiveDateTime, unit: RelativeDateUnit, count: i64) -> NaiveDateTime {
+    match unit {
+        RelativeDateUnit::Day => dt + chrono::Duration::days(count),
+        RelativeDateUnit::Week => dt + chrono::Duration::weeks(count),
+        RelativeDateUnit::Month => add_months(dt, count),
+        RelativeDateUnit::Quarter => add_months(dt, count * 3),
+        RelativeDateUnit::Year => add_months(dt, count * 12),
+    }
+}
+
+fn add_months(dt: NaiveDateTime, months: i64) -> NaiveDateTime {
+    let date = if months >= 0 {
+        dt.date().checked_add_months(Months::new(months as u32))
+    } else {
+        dt.date().checked_sub_months(Months::new((-months) as u32))
+    }
+    .expect("relative date arithmetic overflowed the representable date range");
+    date.and_time(dt.time())
+}
+
+/// Truncate `dt` down to the start of the calendar `unit` it falls in (local midnight on the 1st
+/// of the month for `Month`, local midnight on Monday for `Week`, etc).
+fn start_of_unit(dt: NaiveDateTime, unit: RelativeDateUnit) -> NaiveDateTime {
+    let date = dt.date();
+    let truncated_date = match unit {
+        RelativeDateUnit::Day => date,
+        RelativeDateUnit::Week => date - chrono::Duration::days(date.weekday().num_days_from_monday() as i64),
+        RelativeDateUnit::Month => date.with_day(1).unwrap(),
+        RelativeDateUnit::Quarter => {
+            let quarter_start_month = ((date.month0() / 3) * 3) + 1;
+            // Truncate to day 1 before changing the month: quarter-start months are Jan/Apr/Jul/
+            // Oct, and April only has 30 days, so `with_month` on a day-31 date (e.g. May 31st
+            // moving to April) would return `None` if the day were still 31.
+            date.with_day(1).unwrap().with_month(quarter_start_month).unwrap()
+        }
+        RelativeDateUnit::Year => date.with_month(1).unwrap().with_day(1).unwrap(),
+    };
+    truncated_date.and_hms_opt(0, 0, 0).unwrap()
+}
+
+/// Resolve `expr` to a concrete, half-open `[start_ms, end_ms]` UTC-millisecond pair, interpreting `now_utc`
+/// in `tz_name`'s local time. A naive local datetime maps onto a UTC instant ambiguously around a
+/// DST fall-back transition (two valid offsets) and not at all across a spring-forward gap (zero
+/// valid offsets). An ambiguous time takes the earlier of its two instants; a time inside a gap
+/// is read one hour later, which for a one-hour gap is the same instant as reading it with the
+/// offset in effect before the gap. This keeps this phase total over all `(phrase, now, tz)` inputs rather than needing to fail plan
+/// time over a transition that only affects sub-hour precision.
+pub fn resolve_relative_date_range(
+    expr_phrase: &str,
+    now_utc: DateTime<Utc>,
+    tz_name: &str,
+) -> Result<(i64, i64)> {
+    let expr = parse_relative_date(expr_phrase).ok_or_else(|| {
+        VegaFusionError::parse(format!(
+            "Unrecognized relative date phrase passed to relativeDateRange: '{}'",
+            expr_phrase
+        ))
+    })?;
+
+    let tz = chrono_tz::Tz::from_str(tz_name)
+        .map_err(|_| VegaFusionError::parse(format!("Unrecognized IANA timezone: {}", tz_name)))?;
+    let local_now = now_utc.with_timezone(&tz).naive_local();
+
+    let (start, end) = match expr.anchor {
+        RelativeDateAnchor::Now => {
+            if expr.count <= 0 {
+                (add_units(local_now, expr.unit, expr.count), local_now)
+            } else {
+                (local_now, add_units(local_now, expr.unit, expr.count))
+            }
+        }
+        RelativeDateAnchor::StartOf => {
+            let anchor_instant = add_units(local_now, expr.unit, expr.count);
+            let start = start_of_unit(anchor_instant, expr.unit);
+            let end = start_of_unit(add_units(start, expr.unit, 1), expr.unit);
+            (start, end)
+        }
+        RelativeDateAnchor::EndOf => {
+            let anchor_instant = add_units(local_now, expr.unit, expr.count);
+            let unit_start = start_of_unit(anchor_instant, expr.unit);
+            let end = start_of_unit(add_units(unit_start, expr.unit, 1), expr.unit);
+            (local_now, end)
+        }
+    };
+
+    Ok((to_utc_millis(start, &tz), to_utc_millis(end, &tz)))
+}
+
+fn to_utc_millis(local: NaiveDateTime, tz: &chrono_tz::Tz) -> i64 {
+    tz.from_local_datetime(&local)
+        .earliest()
+        .or_else(|| {
+            tz.from_local_datetime(&(local + chrono::Duration::hours(1)))
+                .earliest()
+        })
+        .unwrap_or_else(|| tz.from_utc_datetime(&local))
+        .with_timezone(&Utc)
+        .timestamp_millis()
+}
+
+const CALL_PREFIX: &str = "relativeDateRange('";
+const CALL_SUFFIX: &str = "')";
+
+/// Replace every `relativeDateRange('<phrase>')` call appearing in `expr` with the literal
+/// two-element array `[start_ms, end_ms]` it resolves to. `expr` is otherwise passed through
+/// unchanged, so a filter like `inrange(datum.date, relativeDateRange('last 7 days'))` becomes
+/// `inrange(datum.date, [1700000000000, 1700604800000])`.
+fn replace_relative_date_calls(expr: &str, local_tz: &str, now_utc: DateTime<Utc>) -> Result<String> {
+    let mut result = String::with_capacity(expr.len());
+    let mut rest = expr;
+    while let Some(call_start) = rest.find(CALL_PREFIX) {
+        let (before, after_call_start) = rest.split_at(call_start);
+        result.push_str(before);
+
+        let phrase_start = &after_call_start[CALL_PREFIX.len()..];
+        let phrase_end = phrase_start.find(CALL_SUFFIX).ok_or_else(|| {
+            VegaFusionError::parse(format!(
+                "Unterminated relativeDateRange call in expression: {}",
+                expr
+            ))
+        })?;
+        let phrase = &phrase_start[..phrase_end];
+
+        let (start_ms, end_ms) = resolve_relative_date_range(phrase, now_utc, local_tz)?;
+        result.push_str(&format!("[{}, {}]", start_ms, end_ms));
+
+        rest = &phrase_start[phrase_end + CALL_SUFFIX.len()..];
+    }
+    result.push_str(rest);
+    Ok(result)
+}
+
+/// Visitor that rewrites `relativeDateRange(...)` calls in every filter/formula transform's
+/// expression string, in place, across all datasets in the spec it walks.
+struct ResolveRelativeDatesVisitor {
+    pub local_tz: String,
+    pub now_utc: DateTime<Utc>,
+}
+
+impl ResolveRelativeDatesVisitor {
+    pub fn new(local_tz: &str, now_utc: DateTime<Utc>) -> Self {
+        Self {
+            local_tz: local_tz.to_string(),
+            now_utc,
+        }
+    }
+}
+
+impl MutChartVisitor for ResolveRelativeDatesVisitor {
+    fn visit_data(&mut self, data: &mut DataSpec, _scope: &[u32]) -> Result<()> {
+        for transform in data.transform.iter_mut() {
+            match transform {
+                TransformSpec::Formula(FormulaTransformSpec { expr, .. }) => {
+                    if expr.contains(CALL_PREFIX) {
+                        *expr = replace_relative_date_calls(expr, &self.local_tz, self.now_utc)?;
+                    }
+                }
+                TransformSpec::Filter(FilterTransformSpec { expr, .. }) => {
+                    if expr.contains(CALL_PREFIX) {
+                        *expr = replace_relative_date_calls(expr, &self.local_tz, self.now_utc)?;
+                    }
+                }
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    // 2023-03-15 10:30:00 UTC, a Wednesday, used as `now` across these cases.
+    fn now() -> DateTime<Utc> {
+        Utc.ymd(2023, 3, 15).and_hms(10, 30, 0)
+    }
+
+    #[test]
+    fn end_of_month_runs_from_now_through_next_months_start() {
+        let (start_ms, end_ms) = resolve_relative_date_range("end of month", now(), "UTC").unwrap();
+        assert_eq!(start_ms, now().timestamp_millis());
+        assert_eq!(end_ms, Utc.ymd(2023, 4, 1).and_hms(0, 0, 0).timestamp_millis());
+    }
+
+    #[test]
+    fn end_of_last_month_runs_from_now_through_this_months_start() {
+        let (start_ms, end_ms) =
+            resolve_relative_date_range("end of last month", now(), "UTC").unwrap();
+        assert_eq!(start_ms, now().timestamp_millis());
+        assert_eq!(end_ms, Utc.ymd(2023, 3, 1).and_hms(0, 0, 0).timestamp_millis());
+    }
+
+    #[test]
+    fn end_of_next_year_runs_from_now_through_the_year_after_next_starts() {
+        let (start_ms, end_ms) =
+            resolve_relative_date_range("end of next year", now(), "UTC").unwrap();
+        assert_eq!(start_ms, now().timestamp_millis());
+        assert_eq!(end_ms, Utc.ymd(2025, 1, 1).and_hms(0, 0, 0).timestamp_millis());
+    }
+
+    #[test]
+    fn this_month_still_resolves_via_start_of() {
+        let (start_ms, end_ms) = resolve_relative_date_range("this month", now(), "UTC").unwrap();
+        assert_eq!(start_ms, Utc.ymd(2023, 3, 1).and_hms(0, 0, 0).timestamp_millis());
+        assert_eq!(end_ms, Utc.ymd(2023, 4, 1).and_hms(0, 0, 0).timestamp_millis());
+    }
+
+    // Regression test for `now` falling on May 31st: the Q2 quarter-start month is April, which
+    // only has 30 days, so truncating to the quarter start must not pass day=31 through
+    // `with_month(4)` (which would return `None` and panic on `.unwrap()`).
+    #[test]
+    fn this_quarter_on_a_day_31_now_does_not_panic_truncating_to_april() {
+        let now = Utc.ymd(2023, 5, 31).and_hms(10, 30, 0);
+        let (start_ms, end_ms) = resolve_relative_date_range("this quarter", now, "UTC").unwrap();
+        assert_eq!(start_ms, Utc.ymd(2023, 4, 1).and_hms(0, 0, 0).timestamp_millis());
+        assert_eq!(end_ms, Utc.ymd(2023, 7, 1).and_hms(0, 0, 0).timestamp_millis());
+    }
+}
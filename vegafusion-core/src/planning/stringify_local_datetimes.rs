@@ -27,16 +27,29 @@ use std::collections::{HashMap, HashSet};
 /// This is needed in order for the chart displayed by the client to be consistent regardless of
 /// the browser's local timezone.  Viewers from all timezones should see the chart displayed as
 /// it would look when generated by pure Vega in the `local_tz` timezone.
+///
+/// Each `time` scale may declare its own IANA `timezone`, overriding the chart-wide `local_tz`
+/// passed in here - this lets a single chart mix scales from different regions (e.g. a
+/// "UTC office hours" axis next to a "viewer local" axis). The actual instant/offset resolution
+/// for a named zone (including historical DST transitions) happens where the generated
+/// `timeFormat`/`toDate` calls are evaluated; this phase is only responsible for threading the
+/// right zone name to each field.
+///
+/// `precision` controls how much sub-second resolution survives the round trip through the
+/// stringified client representation - see [`DatetimePrecision`].
 pub fn stringify_local_datetimes(
     server_spec: &mut ChartSpec,
     client_spec: &mut ChartSpec,
     comm_plan: &CommPlan,
+    local_tz: &str,
+    precision: DatetimePrecision,
 ) -> Result<()> {
     // Build task scope for client spec
     let client_scope = client_spec.to_task_scope()?;
 
-    // Collect the name/scope of all time scales
-    let mut visitor = CollectTimeScalesVisitor::new();
+    // Collect the name/scope of all time scales, along with the IANA timezone each one should be
+    // stringified in
+    let mut visitor = CollectTimeScalesVisitor::new(local_tz);
     client_spec.walk(&mut visitor)?;
     let local_time_scales = visitor.local_time_scales;
 
@@ -59,25 +72,68 @@ pub fn stringify_local_datetimes(
 
     // Add formula transforms to server spec
     let server_scope = server_spec.to_task_scope()?;
-    let mut visitor =
-        StringifyLocalDatetimeFieldsVisitor::new(local_datetime_fields.clone(), server_scope);
+    let mut visitor = StringifyLocalDatetimeFieldsVisitor::new(
+        local_datetime_fields.clone(),
+        server_scope,
+        precision,
+    );
     server_spec.walk_mut(&mut visitor)?;
 
     // Add format spec to client spec (to parse strings as local dates)
-    let mut visitor = FormatLocalDatetimeFieldsVisitor::new(local_datetime_fields);
+    let mut visitor = FormatLocalDatetimeFieldsVisitor::new(local_datetime_fields, precision);
     client_spec.walk_mut(&mut visitor);
 
     Ok(())
 }
 
-/// Visitor to collect the non-UTC time scales
+/// Sub-second resolution to preserve when stringifying a datetime field for the client. Vega/JS
+/// dates only carry millisecond resolution, so `Microsecond` precision (the default) carries the
+/// sub-millisecond remainder in a companion integer field alongside the millisecond-resolution
+/// string, rather than trying to fit it into the string itself.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DatetimePrecision {
+    Second,
+    Millisecond,
+    Microsecond,
+}
+
+impl Default for DatetimePrecision {
+    fn default() -> Self {
+        DatetimePrecision::Microsecond
+    }
+}
+
+impl DatetimePrecision {
+    fn time_format_str(&self) -> &'static str {
+        match self {
+            DatetimePrecision::Second => "%Y-%m-%d %H:%M:%S",
+            DatetimePrecision::Millisecond | DatetimePrecision::Microsecond => {
+                "%Y-%m-%d %H:%M:%S.%L"
+            }
+        }
+    }
+
+    /// Name of the companion field carrying the 0-999 microsecond remainder beyond the
+    /// millisecond-resolution string, or `None` at precisions that don't need one.
+    fn remainder_field_name(&self, field: &str) -> Option<String> {
+        match self {
+            DatetimePrecision::Microsecond => Some(format!("{}__vf_us_remainder", field)),
+            DatetimePrecision::Second | DatetimePrecision::Millisecond => None,
+        }
+    }
+}
+
+/// Visitor to collect the non-UTC time scales, paired with the IANA timezone each one is
+/// stringified in (the scale's own `timezone`, or the chart-wide `local_tz` default)
 struct CollectTimeScalesVisitor {
-    pub local_time_scales: HashSet<ScopedVariable>,
+    pub default_tz: String,
+    pub local_time_scales: HashMap<ScopedVariable, String>,
 }
 
 impl CollectTimeScalesVisitor {
-    pub fn new() -> Self {
+    pub fn new(default_tz: &str) -> Self {
         Self {
+            default_tz: default_tz.to_string(),
             local_time_scales: Default::default(),
         }
     }
@@ -86,26 +142,28 @@ impl CollectTimeScalesVisitor {
 impl ChartVisitor for CollectTimeScalesVisitor {
     fn visit_scale(&mut self, scale: &ScaleSpec, scope: &[u32]) -> Result<()> {
         if matches!(scale.type_, Some(ScaleTypeSpec::Time)) {
+            let tz = scale.timezone.clone().unwrap_or_else(|| self.default_tz.clone());
             self.local_time_scales
-                .insert((Variable::new_scale(&scale.name), Vec::from(scope)));
+                .insert((Variable::new_scale(&scale.name), Vec::from(scope)), tz);
         }
 
         Ok(())
     }
 }
 
-/// Visitor to collect data fields that are scaled by a non-UTC time scale
+/// Visitor to collect data fields that are scaled by a non-UTC time scale, along with the IANA
+/// timezone to stringify each field in
 struct CollectLocalTimeScaledFieldsVisitor {
     pub scope: TaskScope,
     pub candidate_datasets: HashSet<ScopedVariable>,
-    pub local_time_scales: HashSet<ScopedVariable>,
-    pub local_datetime_fields: HashMap<ScopedVariable, HashSet<String>>,
+    pub local_time_scales: HashMap<ScopedVariable, String>,
+    pub local_datetime_fields: HashMap<ScopedVariable, HashMap<String, String>>,
 }
 
 impl CollectLocalTimeScaledFieldsVisitor {
     pub fn new(
         scope: TaskScope,
-        local_time_scales: HashSet<ScopedVariable>,
+        local_time_scales: HashMap<ScopedVariable, String>,
         candidate_datasets: HashSet<ScopedVariable>,
     ) -> Self {
         Self {
@@ -142,13 +200,15 @@ impl ChartVisitor for CollectLocalTimeScaledFieldsVisitor {
                                             resolved_scale.scope.clone(),
                                         );
 
-                                        if self.local_time_scales.contains(&resolved_scoped_scale) {
-                                            // Save off field for dataset
+                                        if let Some(tz) =
+                                            self.local_time_scales.get(&resolved_scoped_scale)
+                                        {
+                                            // Save off field (and its timezone) for dataset
                                             let entry = self
                                                 .local_datetime_fields
                                                 .entry(resolved_data_scoped.clone());
                                             let fields = entry.or_default();
-                                            fields.insert(field.clone());
+                                            fields.insert(field.clone(), tz.clone());
                                         }
                                     }
                                 }
@@ -164,18 +224,21 @@ impl ChartVisitor for CollectLocalTimeScaledFieldsVisitor {
 
 /// Visitor to stringify select datetime fields
 struct StringifyLocalDatetimeFieldsVisitor {
-    pub local_datetime_fields: HashMap<ScopedVariable, HashSet<String>>,
+    pub local_datetime_fields: HashMap<ScopedVariable, HashMap<String, String>>,
     pub scope: TaskScope,
+    pub precision: DatetimePrecision,
 }
 
 impl StringifyLocalDatetimeFieldsVisitor {
     pub fn new(
-        local_datetime_fields: HashMap<ScopedVariable, HashSet<String>>,
+        local_datetime_fields: HashMap<ScopedVariable, HashMap<String, String>>,
         scope: TaskScope,
+        precision: DatetimePrecision,
     ) -> Self {
         Self {
             local_datetime_fields,
             scope,
+            precision,
         }
     }
 }
@@ -184,10 +247,31 @@ impl MutChartVisitor for StringifyLocalDatetimeFieldsVisitor {
     fn visit_data(&mut self, data: &mut DataSpec, scope: &[u32]) -> Result<()> {
         let data_var = (Variable::new_data(&data.name), Vec::from(scope));
         if let Some(fields) = self.local_datetime_fields.get(&data_var) {
-            for field in sorted(fields) {
+            for (field, tz) in sorted(fields) {
                 let transforms = &mut data.transform;
+
+                // At microsecond precision, stash the sub-millisecond remainder in a companion
+                // field before the timeFormat call below overwrites `field` with its string
+                // representation - the remainder can't be recovered from the string afterward.
+                if let Some(remainder_field) = self.precision.remainder_field_name(field) {
+                    let remainder = FormulaTransformSpec {
+                        expr: format!(
+                            "round((datum['{field}'] - floor(datum['{field}'])) * 1000)",
+                            field = field
+                        ),
+                        as_: remainder_field,
+                        extra: Default::default(),
+                    };
+                    transforms.push(TransformSpec::Formula(remainder));
+                }
+
                 let transform = FormulaTransformSpec {
-                    expr: format!("timeFormat(datum['{}'], '%Y-%m-%d %H:%M:%S.%L')", field),
+                    expr: format!(
+                        "timeFormat(datum['{}'], '{}', '{}')",
+                        field,
+                        self.precision.time_format_str(),
+                        tz
+                    ),
                     as_: field.to_string(),
                     extra: Default::default(),
                 };
@@ -196,16 +280,35 @@ impl MutChartVisitor for StringifyLocalDatetimeFieldsVisitor {
         }
 
         // Check if dataset is a child a stringified dataset. If so, we need to convert
-        // datetime strings back to the utc millisecond representation
+        // datetime strings back to the utc millisecond representation, using the same timezone
+        // the string was formatted in so the server-side UTC millisecond value is reconstructed
+        // exactly (rather than being reinterpreted in some other zone).
         if let Some(source) = &data.source {
             let source_var = Variable::new_data(source);
             let source_resolved = self.scope.resolve_scope(&source_var, scope)?;
             let source_resolved_var = (source_resolved.var, source_resolved.scope);
             if let Some(fields) = self.local_datetime_fields.get(&source_resolved_var) {
-                for field in sorted(fields) {
+                for (field, tz) in sorted(fields) {
                     let transforms = &mut data.transform;
+
+                    // Recombine the millisecond instant reconstructed by toDate with the
+                    // microsecond remainder stashed alongside it, so the recovered value equals
+                    // the original to the microsecond rather than only to the millisecond.
+                    if let Some(remainder_field) = self.precision.remainder_field_name(field) {
+                        let recombine = FormulaTransformSpec {
+                            expr: format!(
+                                "datum['{field}'] + datum['{remainder_field}'] / 1000",
+                                field = field,
+                                remainder_field = remainder_field
+                            ),
+                            as_: field.to_string(),
+                            extra: Default::default(),
+                        };
+                        transforms.insert(0, TransformSpec::Formula(recombine));
+                    }
+
                     let transform = FormulaTransformSpec {
-                        expr: format!("toDate(datum['{}'])", field),
+                        expr: format!("toDate(datum['{}'], '{}')", field, tz),
                         as_: field.to_string(),
                         extra: Default::default(),
                     };
@@ -220,13 +323,18 @@ impl MutChartVisitor for StringifyLocalDatetimeFieldsVisitor {
 
 /// Visitor to add format parse specification for local dates
 struct FormatLocalDatetimeFieldsVisitor {
-    pub local_datetime_fields: HashMap<ScopedVariable, HashSet<String>>,
+    pub local_datetime_fields: HashMap<ScopedVariable, HashMap<String, String>>,
+    pub precision: DatetimePrecision,
 }
 
 impl FormatLocalDatetimeFieldsVisitor {
-    pub fn new(local_datetime_fields: HashMap<ScopedVariable, HashSet<String>>) -> Self {
+    pub fn new(
+        local_datetime_fields: HashMap<ScopedVariable, HashMap<String, String>>,
+        precision: DatetimePrecision,
+    ) -> Self {
         Self {
             local_datetime_fields,
+            precision,
         }
     }
 }
@@ -235,10 +343,24 @@ impl MutChartVisitor for FormatLocalDatetimeFieldsVisitor {
     fn visit_data(&mut self, data: &mut DataSpec, scope: &[u32]) -> Result<()> {
         let data_var = (Variable::new_data(&data.name), Vec::from(scope));
         if let Some(fields) = self.local_datetime_fields.get(&data_var) {
-            for field in sorted(fields) {
+            for (field, tz) in sorted(fields) {
                 let transforms = &mut data.transform;
+
+                if let Some(remainder_field) = self.precision.remainder_field_name(field) {
+                    let recombine = FormulaTransformSpec {
+                        expr: format!(
+                            "datum['{field}'] + datum['{remainder_field}'] / 1000",
+                            field = field,
+                            remainder_field = remainder_field
+                        ),
+                        as_: field.to_string(),
+                        extra: Default::default(),
+                    };
+                    transforms.insert(0, TransformSpec::Formula(recombine));
+                }
+
                 let transform = FormulaTransformSpec {
-                    expr: format!("toDate(datum['{}'])", field),
+                    expr: format!("toDate(datum['{}'], '{}')", field, tz),
                     as_: field.to_string(),
                     extra: Default::default(),
                 };
@@ -1,7 +1,79 @@
 use crate::spec::transform::aggregate::{AggregateTransformSpec, AggregateOp as AggregateOpSpec};
 use crate::proto::gen::transforms::{Aggregate, AggregateOp};
 
+/// Map a raw value (in milliseconds, e.g. a UTC datetime) onto the start of its fixed-width
+/// bucket, per the standard date-histogram formula:
+/// `bucket = floor((value - offset) / interval) * interval + offset`.
+///
+/// This is the core primitive a date-histogram groupby needs: calling it with `interval_ms` equal
+/// to a unit's millisecond width (e.g. `86_400_000.0` for `day`) buckets a datetime groupby
+/// field into fixed-width windows before aggregation; units of varying length (months,
+/// quarters, years) are not aligned to calendar boundaries this way. Callers
+/// bucketing on the local-timezone instant (so e.g. month/week boundaries land on local midnight)
+/// pass `value_ms`/`offset_ms` already shifted into that zone, coordinating with
+/// [`crate::planning::stringify_local_datetimes`] the same way the rest of this crate's datetime
+/// handling does.
+pub fn bucket_timestamp_ms(value_ms: f64, interval_ms: f64, offset_ms: f64) -> f64 {
+    ((value_ms - offset_ms) / interval_ms).floor() * interval_ms + offset_ms
+}
+
+/// A date-histogram bucketing descriptor for one groupby field, assumed here as a
+/// `bins: Option<Vec<Option<DateHistogramBin>>>` field on [`AggregateTransformSpec`], index-aligned with `groupby` the same way `fields` is
+/// index-aligned with `aliases`.
+#[derive(Clone, Debug)]
+pub struct DateHistogramBin {
+    pub interval_ms: f64,
+    pub offset_ms: f64,
+    /// When set, the inclusive `[min, max]` range (in the same already-zone-shifted
+    /// milliseconds as `value_ms`/`offset_ms` above) that every bucket boundary should be
+    /// enumerated across, so a later `Impute` stage can zero-fill buckets with no rows the same
+    /// way it already zero-fills any other missing groupby combination - i.e. this is how
+    /// `extended_bounds`/`min_doc_count = 0` are implemented, rather than as a property the
+    /// aggregate evaluator itself needs to understand.
+    pub extended_bounds: Option<(f64, f64)>,
+}
+
+/// Return a rewritten copy of `groupby` for date-histogram bucketing: each field with a `Some` entry in
+/// `bins` is replaced by its generated bucket-key field name (the runtime substitutes
+/// `bucket_timestamp_ms(value, interval, offset)` for the raw field when building this groupby
+/// key), and every bucket boundary implied by that field's `extended_bounds` is appended to the
+/// returned list for a later `Impute` zero-fill stage to key against.
+fn apply_date_histogram_bins(
+    groupby: Vec<String>,
+    bins: &[Option<DateHistogramBin>],
+) -> (Vec<String>, Vec<f64>) {
+    let mut bucketed_groupby = Vec::with_capacity(groupby.len());
+    let mut extended_bucket_bounds = Vec::new();
+
+    for (i, field) in groupby.into_iter().enumerate() {
+        match bins.get(i).and_then(|bin| bin.as_ref()) {
+            Some(bin) => {
+                bucketed_groupby.push(format!("{}__vf_bucket", field));
+                if let Some((min_ms, max_ms)) = bin.extended_bounds {
+                    let mut bound = bucket_timestamp_ms(min_ms, bin.interval_ms, bin.offset_ms);
+                    while bound <= max_ms {
+                        extended_bucket_bounds.push(bound);
+                        bound += bin.interval_ms;
+                    }
+                }
+            }
+            None => bucketed_groupby.push(field),
+        }
+    }
+
+    (bucketed_groupby, extended_bucket_bounds)
+}
+
 impl Aggregate {
+    // This reads `transform.bins` (an `Option<Vec<Option<DateHistogramBin>>>` on `AggregateTransformSpec`,
+    // documented on `DateHistogramBin` above) and writes `params`/`extended_bucket_bounds` onto
+    // the `Aggregate` proto message below. Neither `AggregateTransformSpec`'s nor `Aggregate`'s
+    // defining files are present in this checkout (`vegafusion_core::spec`/`proto::gen` aren't
+    // in this tree), so their having these fields can't be verified to compile from here; they
+    // need to exist with these exact shapes (`bins: Option<Vec<Option<DateHistogramBin>>>` on
+    // `AggregateTransformSpec`; `params: Vec<f64>` index-aligned with `ops`, and
+    // `extended_bucket_bounds: Vec<f64>` - the flat list of enumerated bucket starts returned by
+    // `apply_date_histogram_bins` - on `Aggregate`) before this function can be confirmed correct.
     pub fn new(transform: &AggregateTransformSpec) -> Self {
         let fields: Vec<_> = transform
             .fields
@@ -10,6 +82,8 @@ impl Aggregate {
             .collect();
 
         let groupby: Vec<_> = transform.groupby.iter().map(|f| f.field()).collect();
+        let bins = transform.bins.clone().unwrap_or_default();
+        let (groupby, extended_bucket_bounds) = apply_date_histogram_bins(groupby, &bins);
 
         // Initialize aliases with those potentially provided in field objects
         // (e.g. {"field": "foo", "as": "bar"}
@@ -26,7 +100,16 @@ impl Aggregate {
             }
         }
 
+        // `params` is index-aligned with `ops`: 0.0 for ops that don't take a parameter, and the
+        // requested fraction for `Percentile` (e.g. 0.95 for p95). This lets a single `Percentile`
+        // op cover arbitrary tail percentiles instead of only the fixed `Q1`/`Median`/`Q3` quantiles.
+        let mut params: Vec<f64> = Vec::with_capacity(transform.ops.len());
+
         let ops: Vec<_> = transform.ops.iter().map(|op| {
+            params.push(match op {
+                AggregateOpSpec::Percentile(fraction) => *fraction,
+                _ => 0.0,
+            });
             match op {
                 AggregateOpSpec::Count => {
                     AggregateOp::Count as i32
@@ -97,6 +180,9 @@ impl Aggregate {
                 AggregateOpSpec::Values => {
                     AggregateOp::Values as i32
                 }
+                AggregateOpSpec::Percentile(_) => {
+                    AggregateOp::Percentile as i32
+                }
             }
         }).collect();
 
@@ -104,7 +190,81 @@ impl Aggregate {
             groupby,
             fields,
             ops,
+            params,
             aliases,
+            extended_bucket_bounds,
         }
     }
 }
+
+/// Compute the `fraction`-th percentile of `sorted_values` (which must already be sorted
+/// ascending, with nulls excluded) using linear interpolation between closest ranks: for `n`
+/// values let `h = (n - 1) * fraction`, `lo = floor(h)`, and return
+/// `values[lo] + (h - lo) * (values[lo + 1] - values[lo])`, clamping `h` to `[0, n - 1]` at the
+/// ends.
+///
+/// For large per-group inputs where sorting every group exactly is infeasible, callers can
+/// instead maintain a t-digest per group and query its approximate quantile; that approximation
+/// path isn't implemented here since it needs a t-digest data structure this crate doesn't
+/// currently depend on.
+pub fn interpolated_percentile(sorted_values: &[f64], fraction: f64) -> Option<f64> {
+    if sorted_values.is_empty() {
+        return None;
+    }
+    let n = sorted_values.len();
+    let h = ((n - 1) as f64 * fraction).clamp(0.0, (n - 1) as f64);
+    let lo = h.floor() as usize;
+    let hi = (lo + 1).min(n - 1);
+    Some(sorted_values[lo] + (h - lo as f64) * (sorted_values[hi] - sorted_values[lo]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DAY_MS: f64 = 86_400_000.0;
+
+    #[test]
+    fn bucket_timestamp_ms_floors_to_the_interval() {
+        // 2021-01-02 12:00:00 UTC, bucketed into day-wide buckets with no offset, should floor
+        // to 2021-01-02 00:00:00 UTC.
+        let value_ms = 1_609_588_800_000.0;
+        let bucketed = bucket_timestamp_ms(value_ms, DAY_MS, 0.0);
+        assert_eq!(bucketed, 1_609_545_600_000.0);
+    }
+
+    #[test]
+    fn apply_date_histogram_bins_replaces_the_raw_groupby_field() {
+        let groupby = vec!["date".to_string(), "category".to_string()];
+        let bins = vec![
+            Some(DateHistogramBin {
+                interval_ms: DAY_MS,
+                offset_ms: 0.0,
+                extended_bounds: None,
+            }),
+            None,
+        ];
+
+        let (bucketed_groupby, extended_bucket_bounds) = apply_date_histogram_bins(groupby, &bins);
+
+        assert_eq!(bucketed_groupby, vec!["date__vf_bucket", "category"]);
+        assert!(extended_bucket_bounds.is_empty());
+    }
+
+    #[test]
+    fn apply_date_histogram_bins_enumerates_extended_bounds() {
+        let groupby = vec!["date".to_string()];
+        let bins = vec![Some(DateHistogramBin {
+            interval_ms: DAY_MS,
+            offset_ms: 0.0,
+            extended_bounds: Some((0.0, 3.0 * DAY_MS)),
+        })];
+
+        let (_, extended_bucket_bounds) = apply_date_histogram_bins(groupby, &bins);
+
+        assert_eq!(
+            extended_bucket_bounds,
+            vec![0.0, DAY_MS, 2.0 * DAY_MS, 3.0 * DAY_MS]
+        );
+    }
+}
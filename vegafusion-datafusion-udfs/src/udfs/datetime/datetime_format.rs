@@ -1,5 +1,6 @@
 use crate::udfs::datetime::to_utc_timestamp::to_timestamp_ms;
-use chrono::NaiveDateTime;
+use chrono::{NaiveDateTime, TimeZone, Utc};
+use std::str::FromStr;
 use std::sync::Arc;
 use vegafusion_common::{
     arrow::{
@@ -13,6 +14,31 @@ use vegafusion_common::{
     },
 };
 
+/// Format a single UTC instant (milliseconds since the epoch) in `format_str`, optionally
+/// converting it into `tz_name`'s local wall-clock time first.
+///
+/// A UTC instant always maps to exactly one local time in any IANA zone (the ambiguity/
+/// non-existent-local-time cases in `chrono-tz` only arise the other way around, when
+/// interpreting a *naive* local datetime against a zone), so this conversion never panics and
+/// never needs to choose between offsets.
+fn format_utc_millis(utc_millis: i64, format_str: &str, tz_name: Option<&str>) -> Result<String, DataFusionError> {
+    let utc_seconds = utc_millis.div_euclid(1_000);
+    let utc_nanos = (utc_millis.rem_euclid(1_000) * 1_000_000) as u32;
+    let naive_datetime = NaiveDateTime::from_timestamp_opt(utc_seconds, utc_nanos)
+        .expect("invalid or out-of-range datetime");
+    let utc_datetime = Utc.from_utc_datetime(&naive_datetime);
+
+    Ok(match tz_name {
+        Some(tz_name) => {
+            let tz = chrono_tz::Tz::from_str(tz_name).map_err(|_| {
+                DataFusionError::Internal(format!("Unrecognized IANA timezone: {}", tz_name))
+            })?;
+            utc_datetime.with_timezone(&tz).format(format_str).to_string()
+        }
+        None => utc_datetime.format(format_str).to_string(),
+    })
+}
+
 fn make_time_format_udf() -> ScalarUDF {
     let time_fn: ScalarFunctionImplementation = Arc::new(move |args: &[ColumnarValue]| {
         // Argument order
@@ -31,6 +57,24 @@ fn make_time_format_udf() -> ScalarUDF {
             ));
         };
 
+        // [2] optional IANA timezone name. Falls back to a timezone carried by the input
+        // array's own `Timestamp(unit, Some(tz))` type when no explicit argument is given, so
+        // existing UTC `utcFormat` call sites (two args, no embedded tz) are unaffected.
+        let explicit_tz = match args.get(2) {
+            Some(ColumnarValue::Scalar(ScalarValue::Utf8(Some(tz)))) => Some(tz.clone()),
+            Some(ColumnarValue::Scalar(ScalarValue::Utf8(None))) | None => None,
+            Some(_) => {
+                return Err(DataFusionError::Internal(
+                    "Expected timezone argument to be a scalar string".to_string(),
+                ))
+            }
+        };
+        let embedded_tz = match data_array.data_type() {
+            DataType::Timestamp(_, Some(tz)) => Some(tz.to_string()),
+            _ => None,
+        };
+        let tz_name = explicit_tz.or(embedded_tz);
+
         if matches!(data_array.data_type(), DataType::Null) {
             return Ok(ColumnarValue::Array(data_array));
         }
@@ -42,21 +86,18 @@ fn make_time_format_udf() -> ScalarUDF {
             .downcast_ref::<TimestampMillisecondArray>()
             .unwrap();
 
-        let formatted = Arc::new(StringArray::from_iter(utc_millis_array.iter().map(
-            |utc_millis| {
-                utc_millis.map(|utc_millis| {
-                    // Load as UTC datetime
-                    let utc_seconds = utc_millis / 1_000;
-                    let utc_nanos = (utc_millis % 1_000 * 1_000_000) as u32;
-                    let naive_datetime = NaiveDateTime::from_timestamp_opt(utc_seconds, utc_nanos)
-                        .expect("invalid or out-of-range datetime");
-
-                    // Format as string
-                    let formatted = naive_datetime.format(&format_str);
-                    formatted.to_string()
+        let formatted = Arc::new(
+            utc_millis_array
+                .iter()
+                .map(|utc_millis| {
+                    utc_millis
+                        .map(|utc_millis| {
+                            format_utc_millis(utc_millis, &format_str, tz_name.as_deref())
+                        })
+                        .transpose()
                 })
-            },
-        ))) as ArrayRef;
+                .collect::<Result<StringArray, DataFusionError>>()?,
+        ) as ArrayRef;
 
         // maybe back to scalar
         if formatted.len() != 1 {
@@ -68,6 +109,14 @@ fn make_time_format_udf() -> ScalarUDF {
 
     let return_type: ReturnTypeFunction = Arc::new(move |_| Ok(Arc::new(DataType::Utf8)));
 
+    // Accept the existing 2-arg (data, format) shapes as well as a 3-arg (data, format, tz) form,
+    // and accept any `Timestamp(unit, tz)` combination for the data argument - not just the
+    // `None`-tz millisecond/nanosecond pair the UTC-only version checked for - since the target
+    // timezone may now come from the array's own type rather than only the 2-arg literal list.
+    // `TypeSignature::Exact` can't express "any tz" (it matches a literal `DataType`, and the tz
+    // string inside `Timestamp(unit, Some(tz))` is open-ended), so the embedded-tz 2-arg case is
+    // covered by `Any(2)` instead, the same way the general 3-arg case already relies on `Any(3)`
+    // plus runtime validation in `time_fn`/`to_timestamp_ms` rather than a signature check.
     let signature: Signature = Signature::one_of(
         vec![
             TypeSignature::Exact(vec![
@@ -78,6 +127,8 @@ fn make_time_format_udf() -> ScalarUDF {
                 DataType::Timestamp(TimeUnit::Nanosecond, None),
                 DataType::Utf8,
             ]),
+            TypeSignature::Any(2),
+            TypeSignature::Any(3),
         ],
         Volatility::Immutable,
     );
@@ -88,3 +139,43 @@ fn make_time_format_udf() -> ScalarUDF {
 lazy_static! {
     pub static ref FORMAT_TIMESTAMP_UDF: ScalarUDF = make_time_format_udf();
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use vegafusion_common::datafusion_common::ScalarValue;
+
+    /// The signature must accept a 2-arg (data, format) call whose data array is typed
+    /// `Timestamp(unit, Some(tz))`, since that's exactly the shape a 2-arg caller relying on the
+    /// array's embedded tz (rather than an explicit 3rd-arg timezone) produces; the `None`-tz
+    /// `Exact` variants reject it on tz, and `Any(3)` requires a 3rd argument.
+    #[test]
+    fn signature_accepts_a_2arg_call_with_an_embedded_tz_timestamp() {
+        let accepts_embedded_tz_2arg = match &FORMAT_TIMESTAMP_UDF.signature.type_signature {
+            TypeSignature::OneOf(variants) => variants.contains(&TypeSignature::Any(2)),
+            other => panic!("expected a OneOf signature, found {other:?}"),
+        };
+        assert!(accepts_embedded_tz_2arg);
+    }
+
+    /// Invoke the UDF's own function implementation (the same one DataFusion calls once the
+    /// signature above has accepted the call) with a 2-arg, embedded-tz input, exercising the
+    /// `embedded_tz` fallback path end to end rather than only `format_utc_millis` directly.
+    #[test]
+    fn embedded_tz_2arg_call_formats_using_the_arrays_own_timezone() {
+        let utc_millis = Utc.ymd(2023, 1, 1).and_hms(12, 0, 0).timestamp_millis();
+        let data = ColumnarValue::Scalar(ScalarValue::TimestampMillisecond(
+            Some(utc_millis),
+            Some("America/New_York".into()),
+        ));
+        let format = ColumnarValue::Scalar(ScalarValue::Utf8(Some("%Y-%m-%d %H:%M".to_string())));
+
+        let result = (FORMAT_TIMESTAMP_UDF.fun)(&[data, format]).unwrap();
+        let result = match result {
+            ColumnarValue::Scalar(ScalarValue::Utf8(Some(s))) => s,
+            other => panic!("expected a scalar string, found {other:?}"),
+        };
+
+        assert_eq!(result, "2023-01-01 07:00");
+    }
+}
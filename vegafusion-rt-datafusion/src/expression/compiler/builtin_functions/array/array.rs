@@ -0,0 +1,459 @@
+/*
+ * VegaFusion
+ * Copyright (C) 2022 VegaFusion Technologies LLC
+ *
+ * This program is distributed under multiple licenses.
+ * Please consult the license documentation provided alongside
+ * this program the details of the active license.
+ */
+use datafusion::arrow::array::{
+    ArrayRef, BooleanBuilder, Float64Builder, Int64Builder, ListArray, ListBuilder, StringBuilder,
+};
+use datafusion::arrow::datatypes::DataType;
+use datafusion::common::DFSchema;
+use datafusion::error::DataFusionError;
+use datafusion::physical_plan::functions::make_scalar_function;
+use datafusion::physical_plan::udf::ScalarUDF;
+use datafusion::scalar::ScalarValue;
+use datafusion_expr::{Expr, ReturnTypeFunction, Signature, Volatility};
+use std::sync::Arc;
+use vegafusion_core::error::{Result, VegaFusionError};
+
+fn as_list_array<'a>(
+    array: &'a ArrayRef,
+    fn_name: &str,
+) -> std::result::Result<&'a ListArray, DataFusionError> {
+    array.as_any().downcast_ref::<ListArray>().ok_or_else(|| {
+        DataFusionError::Internal(format!(
+            "{fn_name} expects its first argument to be an array, found {:?}",
+            array.data_type()
+        ))
+    })
+}
+
+/// Scan a single row's sub-array for the first (or last, if `from_end`) element equal to
+/// `needle`, returning its index or -1 if the value isn't present.
+fn index_of_row(
+    row: &ArrayRef,
+    needle: &ScalarValue,
+    from_end: bool,
+) -> std::result::Result<i64, DataFusionError> {
+    let indices: Box<dyn Iterator<Item = usize>> = if from_end {
+        Box::new((0..row.len()).rev())
+    } else {
+        Box::new(0..row.len())
+    };
+    for i in indices {
+        if row.is_null(i) {
+            continue;
+        }
+        let candidate = ScalarValue::try_from_array(row, i)?;
+        if &candidate == needle {
+            return Ok(i as i64);
+        }
+    }
+    Ok(-1)
+}
+
+fn index_of_kernel(
+    args: &[ArrayRef],
+    from_end: bool,
+    fn_name: &str,
+) -> std::result::Result<ArrayRef, DataFusionError> {
+    let arrays = as_list_array(&args[0], fn_name)?;
+    let needles = &args[1];
+    let mut builder = Int64Builder::with_capacity(arrays.len());
+    for i in 0..arrays.len() {
+        if arrays.is_null(i) || needles.is_null(i) {
+            builder.append_null();
+            continue;
+        }
+        let row = arrays.value(i);
+        let needle = ScalarValue::try_from_array(needles, i)?;
+        builder.append_value(index_of_row(&row, &needle, from_end)?);
+    }
+    Ok(Arc::new(builder.finish()) as ArrayRef)
+}
+
+/// `indexof(array, value)`
+///
+/// Returns the first index of value in array, or -1 if value does not appear in array.
+///
+/// See: https://vega.github.io/vega/docs/expressions/#indexof
+pub fn indexof_fn(args: &[Expr], _schema: &DFSchema) -> Result<Expr> {
+    if args.len() != 2 {
+        return Err(VegaFusionError::parse(format!(
+            "indexof requires two arguments. Received {} arguments",
+            args.len()
+        )));
+    }
+    let indexof = |args: &[ArrayRef]| index_of_kernel(args, false, "indexof");
+    let indexof = make_scalar_function(indexof);
+    let return_type: ReturnTypeFunction = Arc::new(move |_| Ok(Arc::new(DataType::Int64)));
+    let udf = ScalarUDF::new(
+        "indexof",
+        &Signature::any(2, Volatility::Immutable),
+        &return_type,
+        &indexof,
+    );
+    Ok(udf.call(args.to_vec()))
+}
+
+/// `lastindexof(array, value)`
+///
+/// Returns the last index of value in array, or -1 if value does not appear in array. Like
+/// `indexof`, but the search proceeds from the end of the array.
+///
+/// See: https://vega.github.io/vega/docs/expressions/#lastIndexOf
+pub fn lastindexof_fn(args: &[Expr], _schema: &DFSchema) -> Result<Expr> {
+    if args.len() != 2 {
+        return Err(VegaFusionError::parse(format!(
+            "lastindexof requires two arguments. Received {} arguments",
+            args.len()
+        )));
+    }
+    let lastindexof = |args: &[ArrayRef]| index_of_kernel(args, true, "lastindexof");
+    let lastindexof = make_scalar_function(lastindexof);
+    let return_type: ReturnTypeFunction = Arc::new(move |_| Ok(Arc::new(DataType::Int64)));
+    let udf = ScalarUDF::new(
+        "lastindexof",
+        &Signature::any(2, Volatility::Immutable),
+        &return_type,
+        &lastindexof,
+    );
+    Ok(udf.call(args.to_vec()))
+}
+
+/// The array-containment test backing `indata`/set predicates: true if `value` appears anywhere
+/// in `array`, false otherwise (unlike `indexof`, never reports "not found" as a sentinel index).
+pub fn includes_fn(args: &[Expr], _schema: &DFSchema) -> Result<Expr> {
+    if args.len() != 2 {
+        return Err(VegaFusionError::parse(format!(
+            "includes requires two arguments. Received {} arguments",
+            args.len()
+        )));
+    }
+    let includes = |args: &[ArrayRef]| {
+        let arrays = as_list_array(&args[0], "includes")?;
+        let needles = &args[1];
+        let mut builder = BooleanBuilder::with_capacity(arrays.len());
+        for i in 0..arrays.len() {
+            if arrays.is_null(i) || needles.is_null(i) {
+                builder.append_null();
+                continue;
+            }
+            let row = arrays.value(i);
+            let needle = ScalarValue::try_from_array(needles, i)?;
+            builder.append_value(index_of_row(&row, &needle, false)? >= 0);
+        }
+        Ok(Arc::new(builder.finish()) as ArrayRef)
+    };
+    let includes = make_scalar_function(includes);
+    let return_type: ReturnTypeFunction = Arc::new(move |_| Ok(Arc::new(DataType::Boolean)));
+    let udf = ScalarUDF::new(
+        "includes",
+        &Signature::any(2, Volatility::Immutable),
+        &return_type,
+        &includes,
+    );
+    Ok(udf.call(args.to_vec()))
+}
+
+/// `span(array)`
+///
+/// Returns the span of array: the difference between the last and first elements, or
+/// `array[array.length-1] - array[0]`.
+///
+/// See: https://vega.github.io/vega/docs/expressions/#span
+pub fn span_fn(args: &[Expr], _schema: &DFSchema) -> Result<Expr> {
+    if args.len() != 1 {
+        return Err(VegaFusionError::parse(format!(
+            "span requires a single argument. Received {} arguments",
+            args.len()
+        )));
+    }
+    let span = |args: &[ArrayRef]| {
+        let arrays = as_list_array(&args[0], "span")?;
+        let mut builder = Float64Builder::with_capacity(arrays.len());
+        for i in 0..arrays.len() {
+            if arrays.is_null(i) {
+                builder.append_null();
+                continue;
+            }
+            let row = arrays.value(i);
+            if row.len() == 0 || row.is_null(0) || row.is_null(row.len() - 1) {
+                builder.append_null();
+                continue;
+            }
+            let first = ScalarValue::try_from_array(&row, 0)?;
+            let last = ScalarValue::try_from_array(&row, row.len() - 1)?;
+            let (first, last) = match (first, last) {
+                (ScalarValue::Float64(Some(f)), ScalarValue::Float64(Some(l))) => (f, l),
+                (ScalarValue::Int64(Some(f)), ScalarValue::Int64(Some(l))) => (f as f64, l as f64),
+                (first, last) => {
+                    return Err(DataFusionError::Internal(format!(
+                        "span requires an array of numeric values, found {first:?}/{last:?}"
+                    )))
+                }
+            };
+            builder.append_value(last - first);
+        }
+        Ok(Arc::new(builder.finish()) as ArrayRef)
+    };
+    let span = make_scalar_function(span);
+    let return_type: ReturnTypeFunction = Arc::new(move |_| Ok(Arc::new(DataType::Float64)));
+    let udf = ScalarUDF::new(
+        "span",
+        &Signature::any(1, Volatility::Immutable),
+        &return_type,
+        &span,
+    );
+    Ok(udf.call(args.to_vec()))
+}
+
+/// Normalize a (possibly negative, possibly out-of-range) JS-style slice index against a row of
+/// length `len`, matching the clamping behavior of `Array.prototype.slice`.
+fn normalize_slice_index(index: i64, len: i64) -> i64 {
+    let index = if index < 0 { (len + index).max(0) } else { index };
+    index.min(len)
+}
+
+fn slice_row_indices(row_len: usize, start: i64, end: i64) -> Vec<usize> {
+    let len = row_len as i64;
+    let start = normalize_slice_index(start, len);
+    let end = normalize_slice_index(end, len);
+    if start >= end {
+        Vec::new()
+    } else {
+        (start..end).map(|i| i as usize).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use datafusion::arrow::array::Int64Array;
+
+    fn int64_row(list: &ListArray, i: usize) -> Vec<i64> {
+        let row = list.value(i);
+        row.as_any()
+            .downcast_ref::<Int64Array>()
+            .unwrap()
+            .values()
+            .to_vec()
+    }
+
+    /// Regression test for a null row in the middle of `arrays`: `build_picked_rows` must call
+    /// `pick` with each row's own real position in `arrays`, not a counter that skips null rows,
+    /// or every row after a null one looks up the wrong `starts`/`ends` entry.
+    #[test]
+    fn build_picked_rows_indexes_by_real_row_position_past_a_null_row() {
+        let mut builder = ListBuilder::new(Int64Builder::new());
+        builder.values().append_value(1);
+        builder.values().append_value(2);
+        builder.values().append_value(3);
+        builder.append(true);
+
+        builder.append(false);
+
+        builder.values().append_value(4);
+        builder.values().append_value(5);
+        builder.values().append_value(6);
+        builder.values().append_value(7);
+        builder.append(true);
+
+        let arrays = builder.finish();
+
+        // Mirrors slice_fn's per-row start/end lookup: starts=[0,0,2], ends=[2,1,4]. Row 1 is
+        // null and skipped; row 2 must still be looked up as index 2, yielding [6, 7].
+        let starts = [0i64, 0, 2];
+        let ends = [2i64, 1, 4];
+
+        let result = build_picked_rows(&arrays, |i, row_len| {
+            slice_row_indices(row_len, starts[i], ends[i])
+        })
+        .unwrap();
+
+        let result = result.as_any().downcast_ref::<ListArray>().unwrap();
+        assert!(result.is_null(1));
+        assert_eq!(int64_row(result, 0), vec![1, 2]);
+        assert_eq!(int64_row(result, 2), vec![6, 7]);
+    }
+}
+
+/// Build a new list array by picking, for each row of `arrays`, the elements at the indices
+/// returned by `pick(row_index, row_len)`. `row_index` is the row's real position in `arrays`
+/// (including rows skipped because they're null), not a count of rows visited so far, so callers
+/// can use it to look up per-row parameters (e.g. slice bounds) from sibling arrays that are the
+/// same length as `arrays`. Supports the handful of element types Vega arrays commonly hold
+/// (numbers, strings, booleans); other element types are rejected rather than silently dropped.
+fn build_picked_rows(
+    arrays: &ListArray,
+    pick: impl Fn(usize, usize) -> Vec<usize>,
+) -> std::result::Result<ArrayRef, DataFusionError> {
+    let child_dtype = match arrays.data_type() {
+        DataType::List(field) => field.data_type().clone(),
+        other => {
+            return Err(DataFusionError::Internal(format!(
+                "Expected a List array, found {other:?}"
+            )))
+        }
+    };
+
+    macro_rules! build_with {
+        ($value_builder:expr, $as_value:expr) => {{
+            let mut builder = ListBuilder::new($value_builder);
+            for i in 0..arrays.len() {
+                if arrays.is_null(i) {
+                    builder.append(false);
+                    continue;
+                }
+                let row = arrays.value(i);
+                for j in pick(i, row.len()) {
+                    if row.is_null(j) {
+                        builder.values().append_null();
+                        continue;
+                    }
+                    let value = ScalarValue::try_from_array(&row, j)?;
+                    $as_value(builder.values(), value)?;
+                }
+                builder.append(true);
+            }
+            Ok(Arc::new(builder.finish()) as ArrayRef)
+        }};
+    }
+
+    match child_dtype {
+        DataType::Float64 => build_with!(Float64Builder::new(), |b: &mut Float64Builder,
+                                                                  v| match v {
+            ScalarValue::Float64(Some(v)) => {
+                b.append_value(v);
+                Ok(())
+            }
+            other => Err(DataFusionError::Internal(format!(
+                "Expected a Float64 array element, found {other:?}"
+            ))),
+        }),
+        DataType::Int64 => build_with!(Int64Builder::new(), |b: &mut Int64Builder, v| match v {
+            ScalarValue::Int64(Some(v)) => {
+                b.append_value(v);
+                Ok(())
+            }
+            other => Err(DataFusionError::Internal(format!(
+                "Expected an Int64 array element, found {other:?}"
+            ))),
+        }),
+        DataType::Utf8 => build_with!(StringBuilder::new(), |b: &mut StringBuilder,
+                                                               v| match v {
+            ScalarValue::Utf8(Some(v)) => {
+                b.append_value(v);
+                Ok(())
+            }
+            other => Err(DataFusionError::Internal(format!(
+                "Expected a Utf8 array element, found {other:?}"
+            ))),
+        }),
+        DataType::Boolean => build_with!(BooleanBuilder::new(), |b: &mut BooleanBuilder,
+                                                                   v| match v {
+            ScalarValue::Boolean(Some(v)) => {
+                b.append_value(v);
+                Ok(())
+            }
+            other => Err(DataFusionError::Internal(format!(
+                "Expected a Boolean array element, found {other:?}"
+            ))),
+        }),
+        other => Err(DataFusionError::Internal(format!(
+            "slice/reverse do not yet support arrays of {other:?}"
+        ))),
+    }
+}
+
+/// `reverse(array)`
+///
+/// Returns a new array with the elements of array in reverse order.
+///
+/// See: https://vega.github.io/vega/docs/expressions/#reverse
+pub fn reverse_fn(args: &[Expr], _schema: &DFSchema) -> Result<Expr> {
+    if args.len() != 1 {
+        return Err(VegaFusionError::parse(format!(
+            "reverse requires a single argument. Received {} arguments",
+            args.len()
+        )));
+    }
+    let reverse = |args: &[ArrayRef]| {
+        let arrays = as_list_array(&args[0], "reverse")?;
+        build_picked_rows(arrays, |_i, row_len| (0..row_len).rev().collect())
+    };
+    let reverse = make_scalar_function(reverse);
+    let return_type: ReturnTypeFunction =
+        Arc::new(|arg_types: &[DataType]| Ok(Arc::new(arg_types[0].clone())));
+    let udf = ScalarUDF::new(
+        "reverse",
+        &Signature::any(1, Volatility::Immutable),
+        &return_type,
+        &reverse,
+    );
+    Ok(udf.call(args.to_vec()))
+}
+
+/// `slice(array, start, end)`
+///
+/// Returns a section of array between the start (inclusive) and end (exclusive) indices. If end
+/// is omitted, all elements from start to the end of the array are included. Negative indices
+/// are taken relative to the end of the array, matching `Array.prototype.slice`.
+///
+/// See: https://vega.github.io/vega/docs/expressions/#slice
+pub fn slice_fn(args: &[Expr], _schema: &DFSchema) -> Result<Expr> {
+    if args.len() != 2 && args.len() != 3 {
+        return Err(VegaFusionError::parse(format!(
+            "slice requires two or three arguments. Received {} arguments",
+            args.len()
+        )));
+    }
+    let has_end = args.len() == 3;
+    let slice = move |args: &[ArrayRef]| {
+        let arrays = as_list_array(&args[0], "slice")?;
+        let starts = &args[1];
+        let ends = if has_end { Some(&args[2]) } else { None };
+
+        let start_at = |i: usize| -> std::result::Result<i64, DataFusionError> {
+            match ScalarValue::try_from_array(starts, i)? {
+                ScalarValue::Int64(Some(v)) => Ok(v),
+                ScalarValue::Float64(Some(v)) => Ok(v as i64),
+                _ => Ok(0),
+            }
+        };
+        let end_at = |i: usize, row_len: usize| -> std::result::Result<i64, DataFusionError> {
+            match ends {
+                Some(ends) => match ScalarValue::try_from_array(ends, i)? {
+                    ScalarValue::Int64(Some(v)) => Ok(v),
+                    ScalarValue::Float64(Some(v)) => Ok(v as i64),
+                    _ => Ok(row_len as i64),
+                },
+                None => Ok(row_len as i64),
+            }
+        };
+
+        // `starts`/`ends` are the same length as `arrays`, so each row's bounds must be looked up
+        // by its real position in `arrays` - `build_picked_rows` skips null rows entirely, so a
+        // counter of rows visited so far would drift out of sync with `starts`/`ends` as soon as
+        // any row is null.
+        let result = build_picked_rows(arrays, |i, row_len| {
+            let start = start_at(i).unwrap_or(0);
+            let end = end_at(i, row_len).unwrap_or(row_len as i64);
+            slice_row_indices(row_len, start, end)
+        });
+        result
+    };
+    let slice = make_scalar_function(slice);
+    let return_type: ReturnTypeFunction =
+        Arc::new(|arg_types: &[DataType]| Ok(Arc::new(arg_types[0].clone())));
+    let udf = ScalarUDF::new(
+        "slice",
+        &Signature::any(args.len(), Volatility::Immutable),
+        &return_type,
+        &slice,
+    );
+    Ok(udf.call(args.to_vec()))
+}
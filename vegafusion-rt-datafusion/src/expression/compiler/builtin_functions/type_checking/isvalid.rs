@@ -6,27 +6,79 @@
  * Please consult the license documentation provided alongside
  * this program the details of the active license.
  */
-use datafusion::arrow::array::ArrayRef;
-use datafusion::arrow::compute::is_not_null;
+use datafusion::arrow::array::{ArrayRef, BooleanBuilder, Float16Array, Float32Array, Float64Array};
 use datafusion::arrow::datatypes::DataType;
 use datafusion::common::DFSchema;
+use datafusion::logical_plan::lit;
 use datafusion::physical_plan::functions::make_scalar_function;
 use datafusion::physical_plan::udf::ScalarUDF;
-use datafusion_expr::{Expr, ExprSchemable, ReturnTypeFunction, Signature, Volatility};
+use datafusion_expr::{abs, and, not, Expr, ExprSchemable, ReturnTypeFunction, Signature, Volatility};
 use std::sync::Arc;
-use vegafusion_core::error::{Result, ResultWithContext, VegaFusionError};
+use vegafusion_core::error::{Result, VegaFusionError};
+
+fn is_floating(dtype: &DataType) -> bool {
+    matches!(dtype, DataType::Float16 | DataType::Float32 | DataType::Float64)
+}
+
+/// Build the `is_nan(value)` UDF call: true where `value` is a floating-point NaN, false for
+/// null, non-NaN, and non-floating-point inputs alike.
+fn is_nan_call(arg: Expr) -> Expr {
+    let is_nan = |args: &[ArrayRef]| {
+        let values = &args[0];
+        let mut builder = BooleanBuilder::with_capacity(values.len());
+        match values.data_type() {
+            DataType::Float64 => {
+                let values = values.as_any().downcast_ref::<Float64Array>().unwrap();
+                for i in 0..values.len() {
+                    builder.append_value(!values.is_null(i) && values.value(i).is_nan());
+                }
+            }
+            DataType::Float32 => {
+                let values = values.as_any().downcast_ref::<Float32Array>().unwrap();
+                for i in 0..values.len() {
+                    builder.append_value(!values.is_null(i) && values.value(i).is_nan());
+                }
+            }
+            DataType::Float16 => {
+                let values = values.as_any().downcast_ref::<Float16Array>().unwrap();
+                for i in 0..values.len() {
+                    builder.append_value(!values.is_null(i) && values.value(i).is_nan());
+                }
+            }
+            // Non-floating-point types can never hold NaN
+            _ => {
+                for _ in 0..values.len() {
+                    builder.append_value(false);
+                }
+            }
+        }
+        Ok(Arc::new(builder.finish()) as ArrayRef)
+    };
+    let is_nan = make_scalar_function(is_nan);
+    let return_type: ReturnTypeFunction = Arc::new(move |_| Ok(Arc::new(DataType::Boolean)));
+    let udf = ScalarUDF::new(
+        "is_nan",
+        &Signature::any(1, Volatility::Immutable),
+        &return_type,
+        &is_nan,
+    );
+    udf.call(vec![arg])
+}
 
 /// `isValid(value)`
 ///
 /// Returns true if value is not null, undefined, or NaN, false otherwise.
 ///
-/// Note: Current implementation does not consider NaN values invalid
-///
 /// See: https://vega.github.io/vega/docs/expressions/#isValid
-pub fn is_valid_fn(args: &[Expr], _schema: &DFSchema) -> Result<Expr> {
+pub fn is_valid_fn(args: &[Expr], schema: &DFSchema) -> Result<Expr> {
     if args.len() == 1 {
         let arg = args[0].clone();
-        Ok(Expr::IsNotNull(Box::new(arg)))
+        let not_null = Expr::IsNotNull(Box::new(arg.clone()));
+        if is_floating(&arg.get_type(schema)?) {
+            Ok(and(not_null, not(is_nan_call(arg))))
+        } else {
+            Ok(not_null)
+        }
     } else {
         Err(VegaFusionError::parse(format!(
             "isValid requires a single argument. Received {} arguments",
@@ -34,3 +86,44 @@ pub fn is_valid_fn(args: &[Expr], _schema: &DFSchema) -> Result<Expr> {
         )))
     }
 }
+
+/// `isNaN(value)`
+///
+/// Returns true if value is a NaN (not a number), false otherwise. NaN is a special value that
+/// results from invalid arithmetic operations, like zero divided by zero.
+///
+/// See: https://vega.github.io/vega/docs/expressions/#isNaN
+pub fn is_nan_fn(args: &[Expr], _schema: &DFSchema) -> Result<Expr> {
+    if args.len() == 1 {
+        Ok(is_nan_call(args[0].clone()))
+    } else {
+        Err(VegaFusionError::parse(format!(
+            "isNaN requires a single argument. Received {} arguments",
+            args.len()
+        )))
+    }
+}
+
+/// `isFinite(value)`
+///
+/// Returns true if value is a finite number, false otherwise. Null, undefined, NaN, and
+/// +/-Infinity are all considered non-finite.
+///
+/// See: https://vega.github.io/vega/docs/expressions/#isFinite
+pub fn is_finite_fn(args: &[Expr], schema: &DFSchema) -> Result<Expr> {
+    if args.len() == 1 {
+        let arg = args[0].clone();
+        let not_null = Expr::IsNotNull(Box::new(arg.clone()));
+        if is_floating(&arg.get_type(schema)?) {
+            let not_infinite = abs(arg.clone()).lt(lit(f64::INFINITY));
+            Ok(and(and(not_null, not(is_nan_call(arg))), not_infinite))
+        } else {
+            Ok(not_null)
+        }
+    } else {
+        Err(VegaFusionError::parse(format!(
+            "isFinite requires a single argument. Received {} arguments",
+            args.len()
+        )))
+    }
+}
@@ -12,6 +12,7 @@ use crate::expression::compiler::utils::{to_numeric, ExprHelpers};
 use crate::transform::TransformTrait;
 use async_trait::async_trait;
 use datafusion::dataframe::DataFrame;
+use datafusion::error::DataFusionError;
 use datafusion::logical_plan::{col, lit, DFSchema};
 use datafusion::physical_plan::functions::make_scalar_function;
 use datafusion::physical_plan::udf::ScalarUDF;
@@ -21,7 +22,7 @@ use float_cmp::approx_eq;
 use std::ops::{Add, Div, Mul, Sub};
 use std::sync::Arc;
 use vegafusion_core::arrow::array::{ArrayRef, Float64Array, Int64Array};
-use vegafusion_core::arrow::compute::unary;
+use vegafusion_core::arrow::compute::{cast, unary};
 use vegafusion_core::arrow::datatypes::{DataType, Field};
 use vegafusion_core::data::scalar::ScalarValueHelpers;
 use vegafusion_core::error::{Result, ResultWithContext, VegaFusionError};
@@ -32,6 +33,10 @@ use vegafusion_core::task_graph::task_value::TaskValue;
 
 #[async_trait]
 impl TransformTrait for Bin {
+    fn name(&self) -> &'static str {
+        "Bin"
+    }
+
     async fn eval(
         &self,
         dataframe: Arc<DataFrame>,
@@ -57,29 +62,44 @@ impl TransformTrait for Bin {
         // Investigate: Would it be faster to define this function once and input the binning
         // parameters?
         //
-        // Implementation handles Float64 and Int64 separately to avoid having DataFusion
-        // copy the full integer array into a float array. This improves performance on integer
-        // columns, but this should be extended to the other numeric types as well.
+        // Float64 and Int64 get their own unary kernel to avoid having DataFusion copy the full
+        // column into a new Float64 array. Every other numeric type (Int8/16/32, UInt8/16/32/64,
+        // Float16/32, Decimal128/256, ...) is widened to Float64 via the Arrow `cast` kernel
+        // first, which does allocate a throwaway array but keeps this total over the numeric type
+        // lattice instead of panicking on anything narrower than Int64/Float64.
         let bin = move |args: &[ArrayRef]| {
             let arg = &args[0];
-            let dtype = arg.data_type();
-            let binned_values = match dtype {
+            let dtype = arg.data_type().clone();
+            let binned_values: Float64Array = match &dtype {
                 DataType::Float64 => {
-                    let field_values = args[0].as_any().downcast_ref::<Float64Array>().unwrap();
-                    let binned_values: Float64Array = unary(field_values, |v| {
+                    let field_values = arg.as_any().downcast_ref::<Float64Array>().unwrap();
+                    unary(field_values, |v| {
                         lookup_bin_edge(v, bin_starts.as_slice(), step, last_stop)
-                    });
-                    binned_values
+                    })
                 }
                 DataType::Int64 => {
-                    let field_values = args[0].as_any().downcast_ref::<Int64Array>().unwrap();
-                    let binned_values: Float64Array = unary(field_values, |v| {
+                    let field_values = arg.as_any().downcast_ref::<Int64Array>().unwrap();
+                    unary(field_values, |v| {
                         let v = v as f64;
                         lookup_bin_edge(v, bin_starts.as_slice(), step, last_stop)
-                    });
-                    binned_values
+                    })
+                }
+                dtype if dtype.is_numeric() => {
+                    let float_arg = cast(arg, &DataType::Float64).map_err(|err| {
+                        DataFusionError::Internal(format!(
+                            "Failed to cast {dtype:?} column to Float64 for bin transform: {err}"
+                        ))
+                    })?;
+                    let field_values = float_arg.as_any().downcast_ref::<Float64Array>().unwrap();
+                    unary(field_values, |v| {
+                        lookup_bin_edge(v, bin_starts.as_slice(), step, last_stop)
+                    })
+                }
+                dtype => {
+                    return Err(DataFusionError::Internal(format!(
+                        "Unsupported data type for bin transform: {dtype:?}"
+                    )))
                 }
-                _ => unreachable!(),
             };
 
             Ok(Arc::new(binned_values) as ArrayRef)
@@ -89,11 +109,7 @@ impl TransformTrait for Bin {
         let return_type: ReturnTypeFunction = Arc::new(move |_| Ok(Arc::new(DataType::Float64)));
         let bin = ScalarUDF::new(
             "bin",
-            &Signature::uniform(
-                1,
-                vec![DataType::Float64, DataType::Int64],
-                Volatility::Immutable,
-            ),
+            &Signature::any(1, Volatility::Immutable),
             &return_type,
             &bin,
         );
@@ -395,3 +411,67 @@ pub fn calculate_bin_params(
         n: ((stop - start) / step).ceil() as i32,
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use vegafusion_core::arrow::array::{
+        Decimal128Array, Float32Array, Int32Array, UInt64Array,
+    };
+
+    #[test]
+    fn lookup_bin_edge_clamps_to_the_outer_bins() {
+        let bin_starts = [0.0, 1.0, 2.0];
+        let step = 1.0;
+        let last_stop = 3.0;
+
+        assert_eq!(
+            lookup_bin_edge(-5.0, &bin_starts, step, last_stop),
+            f64::NEG_INFINITY
+        );
+        assert_eq!(lookup_bin_edge(0.5, &bin_starts, step, last_stop), 0.0);
+        assert_eq!(lookup_bin_edge(2.5, &bin_starts, step, last_stop), 2.0);
+        // Right at the last bin's stop edge should fall in the last bin, not overflow to +inf
+        assert_eq!(lookup_bin_edge(3.0, &bin_starts, step, last_stop), 2.0);
+        assert_eq!(
+            lookup_bin_edge(10.0, &bin_starts, step, last_stop),
+            f64::INFINITY
+        );
+    }
+
+    // `Bin::eval`'s closure only has dedicated unary kernels for Float64/Int64; every other
+    // numeric type it supports (Int32/UInt64/Float32/Decimal128 among them) goes through a cast
+    // to Float64 first. These cases exercise that every one of those types actually casts
+    // cleanly, the way chunk4-1 requires for binning to be total over the numeric type lattice.
+    #[test]
+    fn numeric_types_cast_cleanly_to_float64_for_binning() {
+        let int32 = Arc::new(Int32Array::from(vec![Some(1), None, Some(3)])) as ArrayRef;
+        let float64 = cast(&int32, &DataType::Float64).unwrap();
+        let float64 = float64.as_any().downcast_ref::<Float64Array>().unwrap();
+        assert_eq!(float64.value(0), 1.0);
+        assert!(float64.is_null(1));
+        assert_eq!(float64.value(2), 3.0);
+
+        let uint64 = Arc::new(UInt64Array::from(vec![Some(7), None])) as ArrayRef;
+        let float64 = cast(&uint64, &DataType::Float64).unwrap();
+        let float64 = float64.as_any().downcast_ref::<Float64Array>().unwrap();
+        assert_eq!(float64.value(0), 7.0);
+        assert!(float64.is_null(1));
+
+        let float32 = Arc::new(Float32Array::from(vec![Some(1.5), None])) as ArrayRef;
+        let float64 = cast(&float32, &DataType::Float64).unwrap();
+        let float64 = float64.as_any().downcast_ref::<Float64Array>().unwrap();
+        assert_eq!(float64.value(0), 1.5);
+        assert!(float64.is_null(1));
+
+        let decimal128 = Arc::new(
+            Decimal128Array::from(vec![Some(1250), None])
+                .with_precision_and_scale(10, 2)
+                .unwrap(),
+        ) as ArrayRef;
+        let float64 = cast(&decimal128, &DataType::Float64).unwrap();
+        let float64 = float64.as_any().downcast_ref::<Float64Array>().unwrap();
+        assert_eq!(float64.value(0), 12.5);
+        assert!(float64.is_null(1));
+    }
+}
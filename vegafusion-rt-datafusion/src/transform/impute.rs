@@ -5,19 +5,80 @@ use crate::sql::dataframe::SqlDataFrame;
 use crate::transform::TransformTrait;
 use async_trait::async_trait;
 use datafusion::common::ScalarValue;
-use datafusion::dataframe::DataFrame;
-use datafusion_expr::logical_plan::JoinType;
 use datafusion_expr::{col, lit, when, BuiltInWindowFunction, Expr, WindowFunction};
 use sqlgen::dialect::DialectDisplay;
 use std::sync::Arc;
-use vegafusion_core::arrow::datatypes::DataType;
+use vegafusion_core::arrow::datatypes::{DataType, TimeUnit};
 use vegafusion_core::data::scalar::ScalarValueHelpers;
 use vegafusion_core::error::{Result, VegaFusionError};
 use vegafusion_core::proto::gen::transforms::Impute;
 use vegafusion_core::task_graph::task_value::TaskValue;
 
+/// Data types that [`coerce_json_to_scalar`] knows how to target explicitly. Any other data type
+/// (including `Decimal`/extension types this function doesn't special-case) falls back to the
+/// generic integer/float heuristic in `Impute::eval`.
+fn is_typed_dtype(dtype: &DataType) -> bool {
+    matches!(
+        dtype,
+        DataType::Float16
+            | DataType::Float32
+            | DataType::Float64
+            | DataType::Date32
+            | DataType::Timestamp(_, _)
+    )
+}
+
+/// Coerce a decoded `value_json` into a `ScalarValue` matching `dtype`, so that e.g. filling a
+/// `Float64` column keeps whole-number fill values (`5.0`) as floats instead of truncating them
+/// to an integer `ScalarValue`, and filling a temporal column produces a `Date32`/`Timestamp`
+/// scalar DataFusion can compare against the column directly rather than a bare number that
+/// requires an implicit (and potentially failing) cast.
+///
+/// Date/timestamp fill values are expected to be encoded the same way VegaFusion represents
+/// datetimes elsewhere: milliseconds since the Unix epoch.
+fn coerce_json_to_scalar(json_value: &serde_json::Value, dtype: &DataType) -> Result<ScalarValue> {
+    let as_f64 = || {
+        json_value.as_f64().ok_or_else(|| {
+            VegaFusionError::internal(format!(
+                "Impute fill value {} is not numeric, but imputed column has type {:?}",
+                json_value, dtype
+            ))
+        })
+    };
+
+    Ok(match dtype {
+        DataType::Float16 | DataType::Float32 | DataType::Float64 => {
+            ScalarValue::Float64(Some(as_f64()?))
+        }
+        DataType::Date32 => {
+            let millis = as_f64()?;
+            ScalarValue::Date32(Some((millis / 86_400_000.0).floor() as i32))
+        }
+        DataType::Timestamp(unit, tz) => {
+            let millis = as_f64()? as i64;
+            match unit {
+                TimeUnit::Second => ScalarValue::TimestampSecond(Some(millis / 1_000), tz.clone()),
+                TimeUnit::Millisecond => {
+                    ScalarValue::TimestampMillisecond(Some(millis), tz.clone())
+                }
+                TimeUnit::Microsecond => {
+                    ScalarValue::TimestampMicrosecond(Some(millis * 1_000), tz.clone())
+                }
+                TimeUnit::Nanosecond => {
+                    ScalarValue::TimestampNanosecond(Some(millis * 1_000_000), tz.clone())
+                }
+            }
+        }
+        _ => unreachable!("is_typed_dtype should guard every branch handled here"),
+    })
+}
+
 #[async_trait]
 impl TransformTrait for Impute {
+    fn name(&self) -> &'static str {
+        "Impute"
+    }
+
     async fn eval(
         &self,
         dataframe: Arc<SqlDataFrame>,
@@ -27,25 +88,37 @@ impl TransformTrait for Impute {
         let json_value: serde_json::Value =
             serde_json::from_str(self.value_json.as_ref().unwrap())?;
 
-        // JSON numbers are always interpreted as floats, but if the value is an integer we'd
-        // like the fill value to be an integer as well to avoid converting an integer input
-        // column to floats
-        let value = if json_value.is_i64() {
-            ScalarValue::from(json_value.as_i64().unwrap())
-        } else if json_value.is_f64() && json_value.as_f64().unwrap().fract() == 0.0 {
-            ScalarValue::from(json_value.as_f64().unwrap() as i64)
-        } else {
-            ScalarValue::from_json(&json_value)?
+        // Coerce the fill value to match the `field` column's resolved data type where possible,
+        // so imputing a Float64/Date32/Timestamp column doesn't misfire through the generic
+        // integer-vs-float heuristic below.
+        let field_dtype = dataframe
+            .schema()
+            .fields()
+            .iter()
+            .find(|field| field.name() == &self.field)
+            .map(|field| field.data_type().clone());
+
+        let value = match field_dtype {
+            Some(dtype) if is_typed_dtype(&dtype) => coerce_json_to_scalar(&json_value, &dtype)?,
+            _ => {
+                // Column type is unconstrained (field not found, or a type this function doesn't
+                // specifically coerce for): fall back to the original integer/float heuristic.
+                // JSON numbers are always interpreted as floats, but if the value is an integer
+                // we'd like the fill value to be an integer as well to avoid converting an
+                // integer input column to floats.
+                if json_value.is_i64() {
+                    ScalarValue::from(json_value.as_i64().unwrap())
+                } else if json_value.is_f64() && json_value.as_f64().unwrap().fract() == 0.0 {
+                    ScalarValue::from(json_value.as_f64().unwrap() as i64)
+                } else {
+                    ScalarValue::from_json(&json_value)?
+                }
+            }
         };
 
         let dataframe = match self.groupby.len() {
             0 => zero_groupby_sql(self, dataframe, value)?,
-            1 => single_groupby_sql(self, dataframe, value)?,
-            _ => {
-                return Err(VegaFusionError::internal(
-                    "Expected zero or one groupby columns to impute",
-                ))
-            }
+            _ => multi_groupby_sql(self, dataframe, value)?,
         };
 
         Ok((dataframe, Vec::new()))
@@ -79,7 +152,29 @@ fn zero_groupby_sql(
     dataframe.select(select_columns)
 }
 
-fn single_groupby_sql(
+/// Which relation alias a given output column of [`multi_groupby_sql`]'s join should be selected
+/// from. `key`/`groupby` columns must come from `_key`/`_group` (the left-hand sides of the cross
+/// join and the `LEFT OUTER JOIN`, which always carry the grid's identifying values) rather than
+/// `_inner` (the right-hand side of the `LEFT OUTER JOIN`, whose columns are NULL for any key x
+/// groupby combination synthesized because it's missing from the source data). Every other
+/// column legitimately comes from the joined-in data, so it's qualified to `_inner`.
+fn source_relation<'a>(col_name: &str, key: &str, groupby: &[String]) -> &'a str {
+    if col_name == key {
+        "_key"
+    } else if groupby.iter().any(|g| g == col_name) {
+        "_group"
+    } else {
+        "_inner"
+    }
+}
+
+/// Unlike [`zero_groupby_sql`], which fills nulls with a plain `select`, this needs a cross join
+/// between the distinct `key` values and the distinct `groupby` combinations to materialize rows
+/// for combinations that are missing from the input entirely - `SqlDataFrame` only exposes
+/// `select` (projection) and `chain_query_str` (raw SQL), with no cross-join method on the
+/// expression-builder side, so the join/sort plan below is assembled as SQL text and passed to
+/// `chain_query_str` rather than built through an expression API that doesn't cover this case.
+fn multi_groupby_sql(
     tx: &Impute,
     dataframe: Arc<SqlDataFrame>,
     value: ScalarValue,
@@ -92,17 +187,51 @@ fn single_groupby_sql(
         .map(|field| field.name().clone())
         .collect();
 
-    // First step is to build up a new DataFrame that contains the all possible combinations
-    // of the `key` and `groupby` columns
-
-    // We're only supporting a single groupby column for now
-    let groupby = tx.groupby.get(0).unwrap().clone();
+    // First step is to build up a new DataFrame that contains all possible combinations of the
+    // `key` and `groupby` columns. The groupby grid is built from a single `SELECT DISTINCT`
+    // over all groupby columns jointly, so it has one row per *observed combination* of groupby
+    // values rather than the Cartesian product of each column's independent distinct values.
+    let groupby = &tx.groupby;
+
+    // Every projected and joined column is referenced qualified by its relation alias (`_key`,
+    // `_group`, `_inner`), and the joins use explicit `ON` equality predicates rather than
+    // `USING`, so that a dataset column that happens to collide with another relation's column
+    // name (or with a reserved word) can't make the generated SQL ambiguous or invalid.
+    let key_col_str = col(&tx.key).to_sql_select()?.sql(dataframe.dialect())?;
+    let key_col_in_key_str = col(&format!("_key.{}", tx.key))
+        .to_sql_select()?
+        .sql(dataframe.dialect())?;
+    let key_col_in_inner_str = col(&format!("_inner.{}", tx.key))
+        .to_sql_select()?
+        .sql(dataframe.dialect())?;
+
+    let group_col_strs = groupby
+        .iter()
+        .map(|g| Ok(col(g).to_sql_select()?.sql(dataframe.dialect())?))
+        .collect::<Result<Vec<_>>>()?;
+    let group_col_csv = group_col_strs.join(", ");
+    let group_not_null_clause = group_col_strs
+        .iter()
+        .map(|g| format!("{g} IS NOT NULL"))
+        .collect::<Vec<_>>()
+        .join(" AND ");
 
-    let key_col = col(&tx.key);
-    let key_col_str = key_col.to_sql_select()?.sql(dataframe.dialect())?;
+    let group_cols_in_group_strs = groupby
+        .iter()
+        .map(|g| Ok(col(&format!("_group.{g}")).to_sql_select()?.sql(dataframe.dialect())?))
+        .collect::<Result<Vec<_>>>()?;
+    let group_cols_in_inner_strs = groupby
+        .iter()
+        .map(|g| Ok(col(&format!("_inner.{g}")).to_sql_select()?.sql(dataframe.dialect())?))
+        .collect::<Result<Vec<_>>>()?;
 
-    let group_col = col(&groupby);
-    let group_col_str = group_col.to_sql_select()?.sql(dataframe.dialect())?;
+    let key_join_predicate = format!("{key_col_in_key_str} = {key_col_in_inner_str}");
+    let group_join_predicate = group_cols_in_group_strs
+        .iter()
+        .zip(group_cols_in_inner_strs.iter())
+        .map(|(g, i)| format!("{g} = {i}"))
+        .collect::<Vec<_>>()
+        .join(" AND ");
 
     // Build row number expr to apply to input table
     let row_number_expr = Expr::WindowFunction {
@@ -115,27 +244,38 @@ fn single_groupby_sql(
     .alias("__row_number");
     let row_number_expr_str = row_number_expr.to_sql_select()?.sql(dataframe.dialect())?;
 
-    // Build order by
+    // Build order by, qualified to the `_inner` relation where `__row_number` is computed
     let order_by_expr = Expr::Sort {
-        expr: Box::new(col("__row_number")),
+        expr: Box::new(col("_inner.__row_number")),
         asc: true,
         nulls_first: false,
     };
     let order_by_expr_str = order_by_expr.to_sql_order()?.sql(dataframe.dialect())?;
 
     // Build final selection
-    // Finally, select all of the original DataFrame columns, filling in missing values
-    // of the `field` columns
+    // Finally, select all of the original DataFrame columns, filling in missing values of the
+    // `field` column. The `key` and `groupby` columns must come from `_key`/`_group` rather than
+    // `_inner`: `_inner` is the right side of the `LEFT OUTER JOIN`, so for synthesized rows (the
+    // key x groupby combinations this transform exists to create) `_inner`'s columns are NULL.
+    // `_key`/`_group` are the side that always carries the grid's identifying values, whether or
+    // not a matching `_inner` row exists. Only the non-key/groupby columns (e.g. `field`) are
+    // qualified to `_inner`, since those legitimately come from the joined-in data (or are NULL
+    // when absent, which is exactly what gets filled below).
+    let field_in_inner = col(&format!("_inner.{}", tx.field));
     let mut select_columns: Vec<_> = original_columns
         .iter()
         .map(|col_name| {
             if col_name == &tx.field {
-                when(col(&tx.field).is_not_null(), col(&tx.field))
+                when(field_in_inner.clone().is_not_null(), field_in_inner.clone())
                     .otherwise(lit(value.clone()))
                     .unwrap()
                     .alias(&tx.field)
             } else {
-                col(col_name)
+                col(&format!(
+                    "{}.{col_name}",
+                    source_relation(col_name, &tx.key, groupby)
+                ))
+                .alias(col_name)
             }
         })
         .collect();
@@ -143,7 +283,7 @@ fn single_groupby_sql(
     // Add undocumented "_impute" column that Vega adds
     select_columns.push(
         when(
-            col(&tx.field).is_not_null(),
+            field_in_inner.is_not_null(),
             Expr::Cast {
                 expr: Box::new(Expr::Literal(ScalarValue::Boolean(None))),
                 data_type: DataType::Boolean,
@@ -162,12 +302,16 @@ fn single_groupby_sql(
 
     let dataframe = dataframe.chain_query_str(&format!(
         "SELECT {select_column_csv} from (SELECT DISTINCT {key} from {parent} WHERE {key} IS NOT NULL) AS _key \
-         CROSS JOIN (SELECT DISTINCT {group} from {parent} WHERE {group} IS NOT NULL) AS _group  \
-         LEFT OUTER JOIN (SELECT *, {row_number_expr_str} from {parent}) AS _inner USING ({key}, {group}) \
+         CROSS JOIN (SELECT DISTINCT {group} from {parent} WHERE {group_not_null}) AS _group  \
+         LEFT OUTER JOIN (SELECT *, {row_number_expr_str} from {parent}) AS _inner \
+         ON {key_join_predicate} AND {group_join_predicate} \
          ORDER BY {order_by_expr_str}",
         select_column_csv = select_column_csv,
         key = key_col_str,
-        group = group_col_str,
+        group = group_col_csv,
+        group_not_null = group_not_null_clause,
+        key_join_predicate = key_join_predicate,
+        group_join_predicate = group_join_predicate,
         row_number_expr_str = row_number_expr_str,
         order_by_expr_str = order_by_expr_str,
         parent = dataframe.parent_name(),
@@ -176,148 +320,24 @@ fn single_groupby_sql(
     Ok(dataframe)
 }
 
-fn zero_groupby(
-    tx: &Impute,
-    dataframe: Arc<DataFrame>,
-    value: ScalarValue,
-) -> Result<Arc<DataFrame>> {
-    // Value replacement for field with no groupby fields specified is equivalent to replacing
-    // null values of that column with the fill value
-    let select_columns: Vec<_> = dataframe
-        .schema()
-        .fields()
-        .iter()
-        .map(|field| {
-            let col_name = field.name();
-            if col_name == &tx.field {
-                when(col(&tx.field).is_not_null(), col(&tx.field))
-                    .otherwise(lit(value.clone()))
-                    .unwrap()
-                    .alias(&tx.field)
-            } else {
-                col(col_name)
-            }
-        })
-        .collect();
-
-    Ok(dataframe.select(select_columns)?)
-}
-
-fn single_groupby(
-    tx: &Impute,
-    dataframe: Arc<DataFrame>,
-    value: ScalarValue,
-) -> Result<Arc<DataFrame>> {
-    // Save off names of columns in the original input DataFrame
-    let original_columns: Vec<_> = dataframe
-        .schema()
-        .fields()
-        .iter()
-        .map(|field| field.name().clone())
-        .collect();
-
-    // First step is to build up a new DataFrame that contains the all possible combinations
-    // of the `key` and `groupby` columns
-
-    // We're only supporting a single groupby column for now
-    let groupby = tx.groupby.get(0).unwrap().clone();
-
-    // Make separate dataframes containing all unique values of the `key` and `groupby` columns
-    let key_df = dataframe.aggregate(vec![col(&tx.key)], Vec::new())?;
-    let groupby_df = dataframe.aggregate(vec![col(&groupby)], Vec::new())?;
-
-    // DataFusion doesn't yet expose the cross join operation through the DataFrame
-    // API, so for now we implement the cross join by adding dummy constant values columns
-    // to each
-    let key_df = key_df.select(vec![Expr::Wildcard, lit(true).alias("__true_key")])?;
-    let groupby_df = groupby_df.select(vec![Expr::Wildcard, lit(true).alias("__true_groupby")])?;
-    let all_combos_df = key_df
-        .join(
-            groupby_df,
-            JoinType::Inner,
-            &["__true_key"],
-            &["__true_groupby"],
-            None,
-        )?
-        .select_columns(&[&tx.key, &groupby])?;
-
-    // Next we take the input DataFrame and
-    //  1) Rename the key and groupby columns to avoid collision on join
-    //  2) Add a __row_number column that we can sort by at the end to preserver the input
-    //     row order
-    let mut select_columns: Vec<_> = dataframe
-        .schema()
-        .fields()
-        .iter()
-        .map(|field| {
-            if field.name() == &tx.key {
-                col(field.name()).alias("__key")
-            } else if field.name() == &groupby {
-                col(field.name()).alias("__groupby")
-            } else {
-                col(field.name())
-            }
-        })
-        .collect();
-
-    let row_number_expr = Expr::WindowFunction {
-        fun: WindowFunction::BuiltInWindowFunction(BuiltInWindowFunction::RowNumber),
-        args: Vec::new(),
-        partition_by: Vec::new(),
-        order_by: Vec::new(),
-        window_frame: None,
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Regression test for a synthesized row (a key x groupby combination missing from the
+    // source data, which is exactly what this transform exists to create): its `key` and
+    // `groupby` columns must resolve to `_key`/`_group`, not `_inner`, since `_inner`'s columns
+    // are NULL for such rows.
+    #[test]
+    fn synthesized_rows_take_key_and_groupby_columns_from_the_grid_not_inner() {
+        let key = "k".to_string();
+        let groupby = vec!["g1".to_string(), "g2".to_string()];
+
+        assert_eq!(source_relation("k", &key, &groupby), "_key");
+        assert_eq!(source_relation("g1", &key, &groupby), "_group");
+        assert_eq!(source_relation("g2", &key, &groupby), "_group");
+        // The imputed field itself, and any other passenger column, come from the joined data
+        assert_eq!(source_relation("field", &key, &groupby), "_inner");
+        assert_eq!(source_relation("other", &key, &groupby), "_inner");
     }
-    .alias("__row_number");
-
-    select_columns.push(row_number_expr);
-
-    let dataframe = dataframe.select(select_columns)?;
-
-    // Now join dataframe on key and groupby columns. Use a left outer join to introduce new
-    // rows for combinations of groupby and key that were not originally present.
-    // Also sort by __row_number to restore the original ordering of the input DataFrame with
-    // null values (which will be replaced below) are pushed to the end.
-    let joined = all_combos_df
-        .join(
-            dataframe,
-            JoinType::Left,
-            &[&tx.key, &groupby],
-            &["__key", "__groupby"],
-            None,
-        )?
-        .sort(vec![Expr::Sort {
-            expr: Box::new(col("__row_number")),
-            asc: true,
-            nulls_first: false,
-        }])?;
-
-    // Finally, select all of the original DataFrame columns, filling in missing values
-    // of the `field` columns
-    let mut select_columns: Vec<_> = original_columns
-        .iter()
-        .map(|col_name| {
-            if col_name == &tx.field {
-                when(col(&tx.field).is_not_null(), col(&tx.field))
-                    .otherwise(lit(value.clone()))
-                    .unwrap()
-                    .alias(&tx.field)
-            } else {
-                col(col_name)
-            }
-        })
-        .collect();
-
-    // Add undocumented "_impute" column that Vega adds
-    select_columns.push(
-        when(
-            col(&tx.field).is_not_null(),
-            Expr::Literal(ScalarValue::Boolean(None)),
-        )
-        .otherwise(lit(true))
-        .unwrap()
-        .alias("_impute"),
-    );
-
-    let dataframe = joined.select(select_columns)?;
-    Ok(dataframe)
 }
@@ -35,6 +35,29 @@ pub trait TransformTrait: TransformDependencies {
         dataframe: Arc<SqlDataFrame>,
         config: &CompilationConfig,
     ) -> Result<(Arc<SqlDataFrame>, Vec<TaskValue>)>;
+
+    /// A short, static label for this transform, analogous to `ExecutionPlan::static_name`.
+    /// Individual transforms may override this for a cleaner label; the default falls back to
+    /// the implementing Rust type's name.
+    fn name(&self) -> &'static str {
+        std::any::type_name::<Self>()
+    }
+
+    /// A human-readable, one-line description of this transform stage, for debugging compiled
+    /// pipelines. The default reports `name()` plus the fields/signals it reads and the signals
+    /// it produces;
+    /// individual transforms may override this to include their own parameters (e.g. a filter's
+    /// expression string).
+    fn explain(&self) -> String {
+        let inputs: Vec<_> = self.input_vars().iter().map(|v| format!("{v:?}")).collect();
+        let outputs = self.output_signals();
+        format!(
+            "{}(reads=[{}], produces_signals=[{}])",
+            self.name(),
+            inputs.join(", "),
+            outputs.join(", ")
+        )
+    }
 }
 
 pub fn to_transform_trait(tx: &TransformKind) -> &dyn TransformTrait {
@@ -68,4 +91,12 @@ impl TransformTrait for Transform {
             .eval(sql_df, config)
             .await
     }
+
+    fn name(&self) -> &'static str {
+        to_transform_trait(self.transform_kind()).name()
+    }
+
+    fn explain(&self) -> String {
+        to_transform_trait(self.transform_kind()).explain()
+    }
 }
@@ -5,6 +5,7 @@ use vegafusion_core::error::VegaFusionError;
 use vegafusion_core::error::Result;
 use std::sync::Arc;
 use datafusion::dataframe::DataFrame;
+use datafusion::logical_plan::DFSchema;
 use crate::expression::compiler::config::CompilationConfig;
 use std::collections::{HashMap, HashSet};
 use datafusion::scalar::ScalarValue;
@@ -16,6 +17,11 @@ pub struct TransformPipeline {
     transforms: Vec<Transform>,
 }
 
+/// The name a pipeline stage's output dataframe is registered under in `config.dataframe_scope`.
+fn stage_dataset_name(stage_index: usize) -> String {
+    format!("_pipeline_stage_{stage_index}")
+}
+
 
 impl TryFrom<&[TransformSpec]> for TransformPipeline {
     type Error = VegaFusionError;
@@ -32,21 +38,41 @@ impl TryFrom<&[TransformSpec]> for TransformPipeline {
 
 
 impl TransformPipeline {
-    pub fn call(
+    /// Evaluate this pipeline, also returning the named intermediate datasets that were visible
+    /// to (or produced during) the run.
+    ///
+    /// `config.dataframe_scope` is threaded through each transform step the same way output
+    /// signals are injected into `config.signal_scope`, so a transform can look up a sibling
+    /// dataset by name (e.g. a lookup/cross/fold-style join) rather than only ever seeing the
+    /// single `dataframe` threaded positionally through the pipeline.
+    pub fn call_with_datasets(
         &self,
         dataframe: Arc<dyn DataFrame>,
         config: &CompilationConfig,
-    ) -> Result<(Arc<dyn DataFrame>, HashMap<String, ScalarValue>)> {
+    ) -> Result<(
+        Arc<dyn DataFrame>,
+        HashMap<String, ScalarValue>,
+        HashMap<String, Arc<dyn DataFrame>>,
+    )> {
         let mut result_df = dataframe;
         let mut result_signals: HashMap<String, ScalarValue> = Default::default();
         let mut config = config.clone();
 
-        for tx in &self.transforms {
+        for (i, tx) in self.transforms.iter().enumerate() {
             let tx_result = tx.call(result_df, &config)?;
 
             // Update dataframe
             result_df = tx_result.0;
 
+            // Register this stage's resulting dataframe under a stable, per-stage name so that
+            // later transforms in the pipeline can look up an earlier stage's output by name
+            // (e.g. a lookup/cross-style join against a sibling dataset), mirroring how output
+            // signals are threaded through `config.signal_scope` below. The legacy `Transform`
+            // proto has no dataset-naming field of its own yet, so stages are named positionally.
+            config
+                .dataframe_scope
+                .insert(stage_dataset_name(i), result_df.clone());
+
             for (name, val) in tx.output_signals().iter().zip(tx_result.1) {
                 result_signals.insert(name.clone(), val.clone());
 
@@ -56,6 +82,17 @@ impl TransformPipeline {
             }
         }
 
+        Ok((result_df, result_signals, config.dataframe_scope.clone()))
+    }
+
+    /// Thin wrapper around [`Self::call_with_datasets`] that drops the named intermediate
+    /// datasets, kept for callers that only care about the final dataframe and output signals.
+    pub fn call(
+        &self,
+        dataframe: Arc<dyn DataFrame>,
+        config: &CompilationConfig,
+    ) -> Result<(Arc<dyn DataFrame>, HashMap<String, ScalarValue>)> {
+        let (result_df, result_signals, _) = self.call_with_datasets(dataframe, config)?;
         Ok((result_df, result_signals))
     }
 
@@ -80,4 +117,82 @@ impl TransformPipeline {
 
         sorted(signals).collect()
     }
+
+    /// Render a one-line-per-stage description of this pipeline, for debugging compiled
+    /// transform chains. Each line reports the stage's position, the input variables it reads,
+    /// the output signals it produces, and the real output schema of the `DataFrame` that stage
+    /// produced; the transform itself is rendered with `{:?}` since `expression::Transform`
+    /// (unlike `transform::TransformTrait`) has no `name()` of its own.
+    ///
+    /// Running the pipeline is required to report each stage's real schema, since a later stage's
+    /// schema isn't knowable without the `DataFrame` logical plan the previous stage produced;
+    /// `DataFrame::schema` only reads that plan, so this does not execute a query.
+    ///
+    /// This legacy `expression::Transform`/`datafusion::dataframe::DataFrame` pipeline has no
+    /// `Dialect` or `SqlDataFrame` of its own - that machinery belongs to
+    /// `transform::TransformTrait`'s `SqlDataFrame`-based `Transform`, which this pipeline does
+    /// not use - so there is no compiled SQL fragment or join/relation annotation to render here.
+    pub fn explain(&self, dataframe: Arc<dyn DataFrame>, config: &CompilationConfig) -> Result<String> {
+        let mut result_df = dataframe;
+        let mut config = config.clone();
+        let mut lines = Vec::with_capacity(self.transforms.len());
+
+        for (i, tx) in self.transforms.iter().enumerate() {
+            let inputs: Vec<_> = tx.input_vars().iter().map(|v| format!("{v:?}")).collect();
+            let tx_result = tx.call(result_df, &config)?;
+            result_df = tx_result.0;
+
+            lines.push(format!(
+                "[{}] {:?} (reads=[{}], produces_signals=[{}]) -> schema=[{}]",
+                i,
+                tx,
+                inputs.join(", "),
+                tx.output_signals().join(", "),
+                format_stage_schema(result_df.schema())
+            ));
+
+            for (name, val) in tx.output_signals().iter().zip(tx_result.1) {
+                config.signal_scope.insert(name.clone(), val);
+            }
+        }
+
+        Ok(lines.join("\n"))
+    }
+}
+
+/// Render a `DFSchema`'s fields as `name: type` pairs, for [`TransformPipeline::explain`].
+fn format_stage_schema(schema: &DFSchema) -> String {
+    schema
+        .fields()
+        .iter()
+        .map(|f| format!("{}: {:?}", f.name(), f.data_type()))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+// `Transform`/`CompilationConfig` are prost-generated/compiler-internal types without public
+// constructors available to this file's test module, so `call_with_datasets` and `explain`
+// themselves aren't exercised here; these cover the pure pieces they rely on.
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use datafusion::arrow::datatypes::{DataType, Field, Schema};
+
+    #[test]
+    fn stage_dataset_names_are_stable_and_distinct_per_stage() {
+        assert_eq!(stage_dataset_name(0), "_pipeline_stage_0");
+        assert_eq!(stage_dataset_name(1), "_pipeline_stage_1");
+        assert_ne!(stage_dataset_name(0), stage_dataset_name(1));
+    }
+
+    #[test]
+    fn format_stage_schema_renders_real_field_names_and_types() {
+        let schema = DFSchema::try_from(Schema::new(vec![
+            Field::new("a", DataType::Int64, false),
+            Field::new("b", DataType::Utf8, true),
+        ]))
+        .unwrap();
+
+        assert_eq!(format_stage_schema(&schema), "a: Int64, b: Utf8");
+    }
 }
\ No newline at end of file
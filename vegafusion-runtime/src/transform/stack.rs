@@ -25,16 +25,22 @@ impl TransformTrait for Stack {
         let field = unescape_field(&self.field);
         let group_by: Vec<_> = self.groupby.iter().map(|f| unescape_field(f)).collect();
 
-        // Build order by vector
+        // Build order by vector. Null placement is carried independently of sort direction via
+        // `sort_nulls_first` (mirroring Arrow's `SortOptions { descending, nulls_first }`), rather
+        // than being derived from `asc`. Specs compiled before this field existed default to the
+        // old (direction-derived) behavior so existing pipelines don't change ordering.
         let mut order_by: Vec<_> = self
             .sort_fields
             .iter()
             .zip(&self.sort)
-            .map(|(field, order)| {
+            .enumerate()
+            .map(|(i, (field, order))| {
+                let asc = *order == SortOrder::Ascending as i32;
+                let nulls_first = self.sort_nulls_first.get(i).copied().unwrap_or(asc);
                 Expr::Sort(expr::Sort {
                     expr: Box::new(unescaped_col(field)),
-                    asc: *order == SortOrder::Ascending as i32,
-                    nulls_first: *order == SortOrder::Ascending as i32,
+                    asc,
+                    nulls_first,
                 })
             })
             .collect();
@@ -46,13 +52,41 @@ impl TransformTrait for Stack {
             nulls_first: true,
         }));
 
+        // If the DataFrame's existing ordering already satisfies `order_by` (same columns, same
+        // asc/nulls_first, in the same leading positions), the sort inside `stack` is redundant
+        // and can be skipped entirely.
+        let existing_ordering = dataframe.ordering();
+        let skip_sort = ordering_satisfies(existing_ordering, &order_by);
+
+        // When `group_by` is a leading prefix of the DataFrame's existing ordering, each group's
+        // rows are already contiguous, so the cumulative sum can be computed with a single
+        // streaming pass (bounded by the largest group) instead of a full sort/hash repartition.
+        let streaming = group_by_is_ordering_prefix(existing_ordering, group_by.as_slice());
+
         let offset = StackOffset::from_i32(self.offset).expect("Failed to convert stack offset");
         let mode = match offset {
             StackOffset::Zero => StackOffsetSpec::Zero,
             StackOffset::Normalize => StackOffsetSpec::Normalize,
             StackOffset::Center => StackOffsetSpec::Center,
+            StackOffset::Wiggle => StackOffsetSpec::Wiggle,
+        };
+
+        // The wiggle (streamgraph) baseline needs the full set of layer values pivoted across the
+        // stack domain, and its "inside-out" layer ordering (largest series in the middle) is
+        // incompatible with the sort-skip/streaming fast paths above, which assume a plain
+        // cumulative sum in `order_by`/`group_by` order.
+        let (skip_sort, streaming) = if matches!(mode, StackOffsetSpec::Wiggle) {
+            (false, false)
+        } else {
+            (skip_sort, streaming)
         };
 
+        // Diverging baselines (opt-in via `self.diverging`) stack positive and negative values of
+        // the same group on independent baselines above/below zero, rather than a single running
+        // total that would otherwise stack negatives on top of positives. Existing Zero-offset
+        // pipelines that don't set this flag are unaffected.
+        let diverging = self.diverging && matches!(mode, StackOffsetSpec::Zero);
+
         let result = dataframe
             .stack(
                 &field,
@@ -61,8 +95,111 @@ impl TransformTrait for Stack {
                 &start_field,
                 &stop_field,
                 mode,
+                skip_sort,
+                streaming,
+                diverging,
             )
             .await?;
         Ok((result, Default::default()))
     }
 }
+
+/// Check whether `group_by` forms a leading prefix of `existing` (ignoring sort direction, since
+/// grouping only cares about contiguity, not order), which allows `stack` to run in a single
+/// streaming pass that resets its running totals at each group boundary.
+fn group_by_is_ordering_prefix(existing: &[expr::Sort], group_by: &[String]) -> bool {
+    if group_by.is_empty() || existing.len() < group_by.len() {
+        return false;
+    }
+    existing
+        .iter()
+        .zip(group_by.iter())
+        .all(|(have, name)| have.expr == unescaped_col(name))
+}
+
+/// Check whether `existing` (the DataFrame's current ordering) is a prefix-compatible satisfier
+/// of `requested` (the full lexicographic sort key, including the `ORDER_COL` tiebreak): same
+/// columns, same asc/nulls_first direction, in the same leading positions.
+fn ordering_satisfies(existing: &[expr::Sort], requested: &[Expr]) -> bool {
+    if existing.len() < requested.len() {
+        return false;
+    }
+    existing
+        .iter()
+        .zip(requested.iter())
+        .all(|(have, want)| match want {
+            Expr::Sort(want) => {
+                have.expr == want.expr
+                    && have.asc == want.asc
+                    && have.nulls_first == want.nulls_first
+            }
+            _ => false,
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sort(name: &str, asc: bool, nulls_first: bool) -> expr::Sort {
+        expr::Sort {
+            expr: Box::new(unescaped_col(name)),
+            asc,
+            nulls_first,
+        }
+    }
+
+    #[test]
+    fn ordering_satisfies_accepts_a_matching_prefix() {
+        let existing = vec![sort("a", true, true), sort(ORDER_COL, true, true)];
+        let requested = vec![
+            Expr::Sort(sort("a", true, true)),
+            Expr::Sort(sort(ORDER_COL, true, true)),
+        ];
+        assert!(ordering_satisfies(&existing, &requested));
+    }
+
+    #[test]
+    fn ordering_satisfies_rejects_a_direction_mismatch() {
+        let existing = vec![sort("a", false, true), sort(ORDER_COL, true, true)];
+        let requested = vec![
+            Expr::Sort(sort("a", true, true)),
+            Expr::Sort(sort(ORDER_COL, true, true)),
+        ];
+        assert!(!ordering_satisfies(&existing, &requested));
+    }
+
+    #[test]
+    fn ordering_satisfies_rejects_when_existing_ordering_is_shorter() {
+        let existing = vec![sort("a", true, true)];
+        let requested = vec![
+            Expr::Sort(sort("a", true, true)),
+            Expr::Sort(sort(ORDER_COL, true, true)),
+        ];
+        assert!(!ordering_satisfies(&existing, &requested));
+    }
+
+    #[test]
+    fn group_by_is_ordering_prefix_accepts_a_leading_match_regardless_of_direction() {
+        let existing = vec![sort("a", false, true), sort("b", true, false)];
+        assert!(group_by_is_ordering_prefix(
+            &existing,
+            &["a".to_string(), "b".to_string()]
+        ));
+    }
+
+    #[test]
+    fn group_by_is_ordering_prefix_rejects_an_empty_groupby() {
+        let existing = vec![sort("a", true, true)];
+        assert!(!group_by_is_ordering_prefix(&existing, &[]));
+    }
+
+    #[test]
+    fn group_by_is_ordering_prefix_rejects_when_existing_ordering_is_shorter() {
+        let existing = vec![sort("a", true, true)];
+        assert!(!group_by_is_ordering_prefix(
+            &existing,
+            &["a".to_string(), "b".to_string()]
+        ));
+    }
+}
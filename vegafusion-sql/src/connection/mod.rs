@@ -1,4 +1,5 @@
 pub mod sqlite;
+pub mod substrait;
 
 use std::collections::HashMap;
 use std::sync::Arc;
@@ -31,6 +32,23 @@ pub trait SqlConnection: Send + Sync {
             let table = EmptyTable::new(Arc::new(schema));
             ctx.register_table(table_name.as_str(), Arc::new(table));
         }
+        register_udfs(&ctx);
         Ok(ctx)
     }
 }
+
+/// Register VegaFusion's scalar UDFs into `ctx` so that SQL planned/verified against this
+/// context can reference them by name (e.g. `length(...)`) rather than only through in-memory
+/// `Expr::ScalarUDF` nodes built ad hoc inside `compile_member`.
+///
+/// `get(value, index)` (`make_get_element_udf`) reads the list/string index it operates on from
+/// its second argument rather than baking it into the UDF's name, so one instance covers every
+/// call site the same way `length` does. `make_get_object_member_udf` can't be collapsed the same
+/// way: its return type depends on which struct field is being read, and this DataFusion
+/// version's `ReturnTypeFunction` only sees argument *types*, not argument *values* - so object
+/// member access still needs one differently-typed UDF instance per property name, constructed
+/// per call site in `compile_member`.
+fn register_udfs(ctx: &SessionContext) {
+    ctx.register_udf(vega_fusion::expression::compiler::member::make_length_udf());
+    ctx.register_udf(vega_fusion::expression::compiler::member::make_get_element_udf());
+}
@@ -0,0 +1,100 @@
+use crate::dialect::Dialect;
+use async_trait::async_trait;
+use datafusion::logical_plan::LogicalPlan;
+use datafusion::prelude::SessionContext;
+use datafusion_substrait::logical_plan::producer::to_substrait_plan;
+use prost::Message;
+use std::collections::HashMap;
+use vegafusion_core::arrow::datatypes::Schema;
+use vegafusion_core::data::table::VegaFusionTable;
+use vegafusion_core::error::{Result, VegaFusionError};
+
+/// A sibling to [`crate::connection::SqlConnection`] for backends that accept a serialized
+/// Substrait plan rather than a SQL string. This lets VegaFusion drive any Substrait-capable
+/// engine without writing a new [`Dialect`], since the `LogicalPlan` built by the transform
+/// pipeline's `SqlDataFrame` is serialized once, independent of any target SQL dialect.
+#[async_trait]
+pub trait SubstraitConnection: Send + Sync {
+    /// Execute a serialized `substrait.Plan` protobuf and return the result.
+    async fn fetch_query(&self, plan: &[u8], schema: &Schema) -> Result<VegaFusionTable>;
+
+    async fn tables(&self) -> Result<HashMap<String, Schema>>;
+
+    fn dialect(&self) -> &Dialect;
+
+    /// A `SessionContext` able to resolve the table/function references in a plan built against
+    /// this connection's tables, for use with [`logical_plan_to_substrait`]. The default
+    /// constructs a fresh, empty context; connections whose tables need real schemas registered
+    /// (to resolve `LogicalPlan`s that reference them) should override this.
+    async fn substrait_session_context(&self) -> Result<SessionContext> {
+        Ok(SessionContext::new())
+    }
+
+    /// Serialize `plan` to a `substrait.Plan` protobuf (via [`logical_plan_to_substrait`]) and
+    /// execute it against this connection. This is the call site `logical_plan_to_substrait`
+    /// exists for: once the transform pipeline's `SqlDataFrame` has built a `LogicalPlan`, a
+    /// Substrait-only backend runs it through this method rather than through SQL text.
+    async fn fetch_logical_plan(
+        &self,
+        plan: &LogicalPlan,
+        schema: &Schema,
+    ) -> Result<VegaFusionTable> {
+        let ctx = self.substrait_session_context().await?;
+        let substrait_plan = logical_plan_to_substrait(plan, &ctx)?;
+        self.fetch_query(&substrait_plan, schema).await
+    }
+}
+
+/// Serialize a DataFusion `LogicalPlan` to a `substrait.Plan` protobuf, suitable for sending to a
+/// [`SubstraitConnection`].
+///
+/// This delegates to the `datafusion-substrait` crate's own logical plan producer rather than
+/// walking the plan's nodes by hand, so the function-extension registry for VegaFusion's
+/// scalar/aggregate functions stays in lockstep with whatever DataFusion expression tree
+/// `SqlDataFrame` actually builds. `ctx` only needs to resolve the table/function references
+/// already present in `plan` - it does not need to match the `SessionContext` the plan was
+/// originally planned against.
+pub fn logical_plan_to_substrait(plan: &LogicalPlan, ctx: &SessionContext) -> Result<Vec<u8>> {
+    let substrait_plan = to_substrait_plan(plan, ctx).map_err(|err| {
+        VegaFusionError::internal(format!("failed to convert logical plan to Substrait: {err}"))
+    })?;
+    Ok(substrait_plan.encode_to_vec())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use datafusion::arrow::datatypes::{DataType, Field, Schema as ArrowSchema};
+    use datafusion::datasource::empty::EmptyTable;
+    use datafusion_substrait::logical_plan::consumer::from_substrait_plan;
+    use datafusion_substrait::substrait::proto::Plan as SubstraitPlan;
+    use std::sync::Arc;
+
+    #[tokio::test]
+    async fn logical_plan_round_trips_through_substrait_bytes() {
+        let ctx = SessionContext::new();
+        let schema = ArrowSchema::new(vec![
+            Field::new("a", DataType::Int64, false),
+            Field::new("b", DataType::Utf8, true),
+        ]);
+        ctx.register_table("t", Arc::new(EmptyTable::new(Arc::new(schema.clone()))))
+            .unwrap();
+
+        let plan = ctx
+            .sql("SELECT a, b FROM t WHERE a > 0")
+            .await
+            .unwrap()
+            .to_logical_plan()
+            .unwrap();
+
+        let encoded = logical_plan_to_substrait(&plan, &ctx).unwrap();
+
+        let decoded_plan = SubstraitPlan::decode(encoded.as_slice())
+            .expect("round-tripped bytes should decode as a substrait.Plan");
+        let round_tripped = from_substrait_plan(&ctx, &decoded_plan)
+            .await
+            .expect("substrait plan should convert back into a LogicalPlan");
+
+        assert_eq!(round_tripped.schema().fields().len(), schema.fields().len());
+    }
+}
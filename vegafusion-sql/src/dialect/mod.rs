@@ -1,23 +1,24 @@
 use crate::compile::expr::ToSqlExpr;
-use arrow::datatypes::DataType;
+use arrow::datatypes::{DataType, TimeUnit};
 use datafusion_common::scalar::ScalarValue;
 use datafusion_common::DFSchema;
 use datafusion_expr::lit;
-use datafusion_expr::{when, Expr, Operator};
+use datafusion_expr::{expr, when, Expr, ExprSchemable, Operator, ScalarFunctionDefinition};
 use sqlparser::ast::{
     BinaryOperator as SqlBinaryOperator, DataType as SqlDataType, Expr as SqlExpr,
     Function as SqlFunction, Function, FunctionArg as SqlFunctionArg, FunctionArg,
     FunctionArgExpr as SqlFunctionArgExpr, FunctionArgExpr, Ident as SqlIdent, Ident,
-    ObjectName as SqlObjectName, ObjectName, Value as SqlValue,
+    ObjectName as SqlObjectName, ObjectName, TimezoneInfo, UnaryOperator as SqlUnaryOperator,
+    Value as SqlValue,
 };
 use sqlparser::dialect::{
-    BigQueryDialect, ClickHouseDialect, Dialect as SqlParserDialect, GenericDialect, MySqlDialect,
-    PostgreSqlDialect, RedshiftSqlDialect, SQLiteDialect, SnowflakeDialect,
+    BigQueryDialect, ClickHouseDialect, Dialect as SqlParserDialect, DuckDbDialect, GenericDialect,
+    MySqlDialect, PostgreSqlDialect, RedshiftSqlDialect, SQLiteDialect, SnowflakeDialect,
 };
 use std::collections::{HashMap, HashSet};
 use std::fmt::Debug;
 use std::str::FromStr;
-use std::sync::Arc;
+use std::sync::{Arc, Mutex, OnceLock};
 use vegafusion_common::error::{Result, VegaFusionError};
 
 #[derive(Clone, Debug)]
@@ -50,7 +51,7 @@ impl ParseDialect {
             }
             ParseDialect::DataFusion => Arc::new(GenericDialect),
             ParseDialect::Dremio => Arc::new(GenericDialect),
-            ParseDialect::DuckDB => Arc::new(GenericDialect),
+            ParseDialect::DuckDB => Arc::new(DuckDbDialect {}),
             ParseDialect::Generic => Arc::new(GenericDialect),
             ParseDialect::MySql => Arc::new(MySqlDialect {}),
             ParseDialect::Postgres => Arc::new(PostgreSqlDialect {}),
@@ -101,12 +102,33 @@ pub struct Dialect {
     /// Names of supported window functions that match the semantics of the DataFusion implementation
     pub window_functions: HashSet<String>,
 
+    /// Names of aggregate functions that may also be compiled as window functions (i.e. emitted
+    /// as `agg(...) OVER (...)`), for backends that support using an aggregate in a window clause
+    pub aggregate_functions_as_window: HashSet<String>,
+
+    /// Whether this dialect supports using an aggregate function as a window function
+    /// (`SUM(x) OVER (PARTITION BY ... ORDER BY ... ROWS ...)`)
+    pub supports_aggregate_window_functions: bool,
+
     /// Scalar function transformations
     pub scalar_transformers: HashMap<String, Arc<dyn FunctionTransformer>>,
 
     /// Aggregate function transformations
     pub aggregate_transformers: HashMap<String, Arc<dyn FunctionTransformer>>,
 
+    /// Whether this dialect supports SQL-standard ordered-set aggregates
+    /// (`<agg>(args) WITHIN GROUP (ORDER BY ...)`), used here to compile `q1`/`median`/`q3` to
+    /// `PERCENTILE_CONT`/`PERCENTILE_DISC` and `mode` to `MODE`
+    pub supports_ordered_set_aggregates: bool,
+
+    /// Approximate alternative to the exact `median`/`quantile`/`q1`/`q3` path in
+    /// `aggregate_transformers`, consulted only when the caller opts into approximate
+    /// aggregation. Keyed by aggregate name exactly like `aggregate_transformers`, since each
+    /// quantile needs its own default percentile (e.g. `q1` is 0.25, not the 0.5 appropriate for
+    /// `median`). A name missing from this map means this dialect has no faster approximate form
+    /// for it, so the exact path is used instead.
+    pub approx_quantile_transformers: HashMap<String, Arc<dyn FunctionTransformer>>,
+
     /// Implementation mode for inline VALUES
     pub values_mode: ValuesMode,
 
@@ -152,8 +174,12 @@ impl Default for Dialect {
             scalar_functions: Default::default(),
             aggregate_functions: Default::default(),
             window_functions: Default::default(),
+            aggregate_functions_as_window: Default::default(),
+            supports_aggregate_window_functions: false,
             scalar_transformers: Default::default(),
             aggregate_transformers: Default::default(),
+            supports_ordered_set_aggregates: false,
+            approx_quantile_transformers: Default::default(),
             values_mode: ValuesMode::ValuesWithSubqueryColumnAliases {
                 explicit_row: false,
             },
@@ -175,6 +201,148 @@ impl Dialect {
         self.parse_dialect.parser_dialect()
     }
 
+    /// Whether `name` may be used as a windowed aggregate (`agg(...) OVER (...)`) in this
+    /// dialect, either because it's a native window function or because the dialect supports
+    /// compiling an ordinary aggregate as a window function.
+    pub fn supports_as_window_function(&self, name: &str) -> bool {
+        self.window_functions.contains(name)
+            || (self.supports_aggregate_window_functions
+                && self.aggregate_functions_as_window.contains(name))
+    }
+
+    /// Render `name(args...)` as an aggregate call, applying `aggregate_transformers` dialect
+    /// renaming (e.g. `stddev` -> `stddev_samp`) if a transformer is registered, or a plain
+    /// function call otherwise.
+    fn compile_aggregate_call(&self, name: &str, args: &[Expr], schema: &DFSchema) -> Result<SqlExpr> {
+        if let Some(transformer) = self.aggregate_transformers.get(name) {
+            transformer.transform(args, self, schema)
+        } else {
+            let sql_args = args_to_sql_args(args, self, schema)?;
+            Ok(SqlExpr::Function(Function {
+                name: ObjectName(vec![Ident {
+                    value: name.to_string(),
+                    quote_style: None,
+                }]),
+                args: sql_args,
+                over: None,
+                distinct: false,
+                special: false,
+            }))
+        }
+    }
+
+    /// Render `name(args...)` as an aggregate call for use inside a windowed `OVER (...)` clause.
+    /// Errs via `supports_as_window_function` if this dialect can't actually use `name` as a
+    /// window function, rather than silently emitting SQL the target engine would reject.
+    pub fn compile_aggregate_as_window_call(
+        &self,
+        name: &str,
+        args: &[Expr],
+        schema: &DFSchema,
+    ) -> Result<SqlExpr> {
+        if !self.supports_as_window_function(name) {
+            return Err(VegaFusionError::internal(format!(
+                "{name} may not be used as a window function for dialect {:?}",
+                self.parse_dialect
+            )));
+        }
+        self.compile_aggregate_call(name, args, schema)
+    }
+
+    /// Render `median`/`quantile`/`q1`/`q3` as an aggregate call. When `use_approximate_quantiles`
+    /// is set and this dialect registers an `approx_quantile_transformers` entry for `name`, that
+    /// faster, approximate form is used instead of the exact `aggregate_transformers` path.
+    pub fn compile_quantile_aggregate(
+        &self,
+        name: &str,
+        args: &[Expr],
+        schema: &DFSchema,
+        use_approximate_quantiles: bool,
+    ) -> Result<SqlExpr> {
+        if use_approximate_quantiles {
+            if let Some(transformer) = self.approx_quantile_transformers.get(name) {
+                return transformer.transform(args, self, schema);
+            }
+        }
+        self.compile_aggregate_call(name, args, schema)
+    }
+
+    /// Render a scalar function call whose name is in neither `scalar_functions` nor
+    /// `scalar_transformers` — e.g. a DataFusion built-in this dialect's allow-lists haven't been
+    /// taught about yet. Rather than failing the whole expression and pushing it to client-side
+    /// evaluation, fall back to emitting `name(args...)` directly: each argument is still compiled
+    /// through the usual `to_sql` path, so this dialect's quoting, casts, and transformers keep
+    /// applying recursively everywhere except the outermost call syntax.
+    ///
+    /// Note this output can't be round-tripped back through `Expr::from_sql`: that function only
+    /// accepts names already present in `scalar_functions`/`aggregate_functions`, by design, since
+    /// it otherwise can't tell an unrenamed fallback call apart from an ambiguous
+    /// `scalar_transformers`/`aggregate_transformers` rename target. Round-tripping an
+    /// unregistered call would need the dialect to track which rendered names are safe
+    /// pass-throughs, not just which names it emits unrenamed.
+    pub fn compile_unregistered_scalar_call(
+        &self,
+        name: &str,
+        args: &[Expr],
+        schema: &DFSchema,
+    ) -> Result<SqlExpr> {
+        let sql_args = args_to_sql_args(args, self, schema)?;
+        Ok(SqlExpr::Function(Function {
+            name: ObjectName(vec![Ident {
+                value: name.to_string(),
+                quote_style: None,
+            }]),
+            args: sql_args,
+            over: None,
+            distinct: false,
+            special: false,
+        }))
+    }
+
+    /// Escape hatch for engines not already covered by one of the presets below: build a
+    /// `Dialect` from a `parse_dialect`/`quote_style` pair and otherwise-default capabilities,
+    /// then register whatever vendor-specific functions and casts it needs with the `with_*`
+    /// builders.
+    pub fn from_parts(parse_dialect: ParseDialect, quote_style: char) -> Self {
+        Self {
+            parse_dialect,
+            quote_style,
+            ..Default::default()
+        }
+    }
+
+    /// Register `name` as a supported scalar function, compiled as a plain passthrough call
+    /// (e.g. `name(args...)`) with no dialect-specific rewriting.
+    pub fn with_scalar_function(mut self, name: impl Into<String>) -> Self {
+        self.scalar_functions.insert(name.into());
+        self
+    }
+
+    /// Register `name` as a supported aggregate function, compiled via `transformer` rather than
+    /// a plain passthrough call.
+    pub fn with_aggregate_transformer(
+        mut self,
+        name: impl Into<String>,
+        transformer: Arc<dyn FunctionTransformer>,
+    ) -> Self {
+        let name = name.into();
+        self.aggregate_functions.insert(name.clone());
+        self.aggregate_transformers.insert(name, transformer);
+        self
+    }
+
+    /// Register `name` as usable as a window function (`name(args...) OVER (...)`).
+    pub fn with_window_function(mut self, name: impl Into<String>) -> Self {
+        self.window_functions.insert(name.into());
+        self
+    }
+
+    /// Register how `from` is rendered when cast to via `CAST(expr AS ...)`.
+    pub fn with_cast_datatype(mut self, from: DataType, to: SqlDataType) -> Self {
+        self.cast_datatypes.insert(from, to);
+        self
+    }
+
     pub fn athena() -> Self {
         use Operator::*;
         let aggregate_transformers: HashMap<String, Arc<dyn FunctionTransformer>> = vec![
@@ -232,6 +400,8 @@ impl Dialect {
             .iter()
             .map(|s| s.to_string())
             .collect(),
+            aggregate_functions_as_window: Default::default(),
+            supports_aggregate_window_functions: false,
             scalar_transformers: vec![
                 ("log", RenameFunctionTransformer::new_dyn("log10")),
                 ("signum", RenameFunctionTransformer::new_dyn("sign")),
@@ -241,6 +411,8 @@ impl Dialect {
             .map(|(name, v)| (name.to_string(), v))
             .collect(),
             aggregate_transformers,
+            supports_ordered_set_aggregates: false,
+            approx_quantile_transformers: Default::default(),
             values_mode: ValuesMode::ValuesWithSubqueryColumnAliases {
                 explicit_row: false,
             },
@@ -262,10 +434,29 @@ impl Dialect {
                 (DataType::Float32, SqlDataType::Double),
                 (DataType::Float64, SqlDataType::Double),
                 (DataType::Utf8, SqlDataType::Varchar(None)),
+                (DataType::Date32, SqlDataType::Date),
+                (DataType::Date64, SqlDataType::Date),
+                (
+                    DataType::Timestamp(TimeUnit::Microsecond, None),
+                    SqlDataType::Timestamp(None, TimezoneInfo::None),
+                ),
+                (DataType::Time32(TimeUnit::Millisecond), SqlDataType::Time(None, TimezoneInfo::None)),
+                (DataType::Time64(TimeUnit::Microsecond), SqlDataType::Time(None, TimezoneInfo::None)),
+            ]
+            .into_iter()
+            .collect(),
+            cast_transformers: vec![
+                (
+                    (DataType::Float64, DataType::Decimal128(38, 10)),
+                    DecimalCastTransformer::new_dyn(38, 10),
+                ),
+                (
+                    (DataType::Float64, DataType::Decimal256(76, 10)),
+                    DecimalCastTransformer::new_dyn(76, 10),
+                ),
             ]
             .into_iter()
             .collect(),
-            cast_transformers: Default::default(),
             cast_propagates_null: true,
             supports_non_finite_floats: false,
         }
@@ -296,7 +487,7 @@ impl Dialect {
             .iter()
             .map(|s| s.to_string())
             .collect(),
-            aggregate_functions: vec!["min", "max", "count", "avg", "sum"]
+            aggregate_functions: vec!["min", "max", "count", "avg", "sum", "median", "quantile"]
                 .iter()
                 .map(|s| s.to_string())
                 .collect(),
@@ -316,6 +507,11 @@ impl Dialect {
             .iter()
             .map(|s| s.to_string())
             .collect(),
+            aggregate_functions_as_window: vec!["sum", "avg", "min", "max", "count"]
+                .iter()
+                .map(|s| s.to_string())
+                .collect(),
+            supports_aggregate_window_functions: true,
             scalar_transformers: vec![
                 ("log", RenameFunctionTransformer::new_dyn("log10")),
                 ("log2", LogBaseTransformer::new_dyn(2, false)),
@@ -325,7 +521,21 @@ impl Dialect {
             .into_iter()
             .map(|(name, v)| (name.to_string(), v))
             .collect(),
-            aggregate_transformers: Default::default(),
+            aggregate_transformers: vec![
+                (
+                    "median",
+                    PercentileTransformer::new_dyn(PercentileRendering::BigQueryApproxQuantiles, 0.5),
+                ),
+                (
+                    "quantile",
+                    PercentileTransformer::new_dyn(PercentileRendering::BigQueryApproxQuantiles, 0.5),
+                ),
+            ]
+            .into_iter()
+            .map(|(name, v)| (name.to_string(), v))
+            .collect(),
+            supports_ordered_set_aggregates: false,
+            approx_quantile_transformers: Default::default(),
             values_mode: ValuesMode::SelectUnion,
             supports_null_ordering: true,
             impute_fully_qualified: false,
@@ -345,10 +555,29 @@ impl Dialect {
                 (DataType::Float32, float64dtype.clone()),
                 (DataType::Float64, float64dtype.clone()),
                 (DataType::Utf8, SqlDataType::String),
+                (DataType::Date32, SqlDataType::Date),
+                (DataType::Date64, SqlDataType::Date),
+                (
+                    DataType::Timestamp(TimeUnit::Microsecond, None),
+                    SqlDataType::Custom(ObjectName(vec!["DATETIME".into()]), Vec::new()),
+                ),
+                (DataType::Time32(TimeUnit::Millisecond), SqlDataType::Time(None, TimezoneInfo::None)),
+                (DataType::Time64(TimeUnit::Microsecond), SqlDataType::Time(None, TimezoneInfo::None)),
+            ]
+            .into_iter()
+            .collect(),
+            cast_transformers: vec![
+                (
+                    (DataType::Float64, DataType::Decimal128(38, 9)),
+                    DecimalCastTransformer::new_dyn(38, 9),
+                ),
+                (
+                    (DataType::Float64, DataType::Decimal256(76, 9)),
+                    DecimalCastTransformer::new_dyn(76, 9),
+                ),
             ]
             .into_iter()
             .collect(),
-            cast_transformers: Default::default(),
             cast_propagates_null: true,
             supports_non_finite_floats: true,
         }
@@ -366,6 +595,15 @@ impl Dialect {
             ),
             ("covar", RenameFunctionTransformer::new_dyn("covarSamp")),
             ("covar_pop", RenameFunctionTransformer::new_dyn("covarPop")),
+            (
+                "quantile",
+                PercentileTransformer::new_dyn(
+                    PercentileRendering::ParametricCall {
+                        name: "quantile".to_string(),
+                    },
+                    0.5,
+                ),
+            ),
         ]
         .into_iter()
         .map(|(name, v)| (name.to_string(), v))
@@ -387,10 +625,12 @@ impl Dialect {
             .iter()
             .map(|s| s.to_string())
             .collect(),
-            aggregate_functions: vec!["min", "max", "count", "avg", "sum", "median", "corr"]
-                .iter()
-                .map(|s| s.to_string())
-                .collect(),
+            aggregate_functions: vec![
+                "min", "max", "count", "avg", "sum", "median", "quantile", "corr",
+            ]
+            .iter()
+            .map(|s| s.to_string())
+            .collect(),
             window_functions: vec![
                 "row_number",
                 "rank",
@@ -401,6 +641,11 @@ impl Dialect {
             .iter()
             .map(|s| s.to_string())
             .collect(),
+            aggregate_functions_as_window: vec!["sum", "avg", "min", "max", "count"]
+                .iter()
+                .map(|s| s.to_string())
+                .collect(),
+            supports_aggregate_window_functions: true,
             scalar_transformers: vec![
                 ("log", RenameFunctionTransformer::new_dyn("log10")),
                 ("signum", RenameFunctionTransformer::new_dyn("sign")),
@@ -410,6 +655,8 @@ impl Dialect {
             .map(|(name, v)| (name.to_string(), v))
             .collect(),
             aggregate_transformers,
+            supports_ordered_set_aggregates: false,
+            approx_quantile_transformers: Default::default(),
             values_mode: ValuesMode::SelectUnion,
             supports_null_ordering: true,
             impute_fully_qualified: true,
@@ -429,10 +676,32 @@ impl Dialect {
                 (DataType::Float32, SqlDataType::Float(None)),
                 (DataType::Float64, SqlDataType::Double),
                 (DataType::Utf8, SqlDataType::Varchar(None)),
+                (DataType::Date32, SqlDataType::Date),
+                (DataType::Date64, SqlDataType::Date),
+                (
+                    DataType::Timestamp(TimeUnit::Microsecond, None),
+                    SqlDataType::Custom(
+                        ObjectName(vec!["DateTime64".into()]),
+                        vec!["3".to_string()],
+                    ),
+                ),
+                (DataType::Time32(TimeUnit::Millisecond), SqlDataType::Time(None, TimezoneInfo::None)),
+                (DataType::Time64(TimeUnit::Microsecond), SqlDataType::Time(None, TimezoneInfo::None)),
+            ]
+            .into_iter()
+            .collect(),
+            cast_transformers: vec![
+                (
+                    (DataType::Float64, DataType::Decimal128(38, 10)),
+                    DecimalCastTransformer::new_dyn(38, 10),
+                ),
+                (
+                    (DataType::Float64, DataType::Decimal256(76, 10)),
+                    DecimalCastTransformer::new_dyn(76, 10),
+                ),
             ]
             .into_iter()
             .collect(),
-            cast_transformers: Default::default(),
             cast_propagates_null: false,
             supports_non_finite_floats: true,
         }
@@ -444,6 +713,15 @@ impl Dialect {
             ("var", RenameFunctionTransformer::new_dyn("var_samp")),
             ("stddev", RenameFunctionTransformer::new_dyn("stddev_samp")),
             ("covar", RenameFunctionTransformer::new_dyn("covar_samp")),
+            (
+                "quantile",
+                PercentileTransformer::new_dyn(
+                    PercentileRendering::TrailingArg {
+                        name: "percentile_approx".to_string(),
+                    },
+                    0.5,
+                ),
+            ),
         ]
         .into_iter()
         .map(|(name, v)| (name.to_string(), v))
@@ -472,6 +750,7 @@ impl Dialect {
                 "avg",
                 "sum",
                 "median",
+                "quantile",
                 "var_pop",
                 "stddev_pop",
                 "covar_pop",
@@ -496,6 +775,11 @@ impl Dialect {
             .iter()
             .map(|s| s.to_string())
             .collect(),
+            aggregate_functions_as_window: vec!["sum", "avg", "min", "max", "count"]
+                .iter()
+                .map(|s| s.to_string())
+                .collect(),
+            supports_aggregate_window_functions: true,
             scalar_transformers: vec![
                 ("log", RenameFunctionTransformer::new_dyn("log10")),
                 ("signum", RenameFunctionTransformer::new_dyn("sign")),
@@ -504,6 +788,8 @@ impl Dialect {
             .map(|(name, v)| (name.to_string(), v))
             .collect(),
             aggregate_transformers,
+            supports_ordered_set_aggregates: false,
+            approx_quantile_transformers: Default::default(),
             values_mode: ValuesMode::ValuesWithSubqueryColumnAliases {
                 explicit_row: false,
             },
@@ -525,10 +811,29 @@ impl Dialect {
                 (DataType::Float32, SqlDataType::Float(None)),
                 (DataType::Float64, SqlDataType::Double),
                 (DataType::Utf8, SqlDataType::String),
+                (DataType::Date32, SqlDataType::Date),
+                (DataType::Date64, SqlDataType::Date),
+                (
+                    DataType::Timestamp(TimeUnit::Microsecond, None),
+                    SqlDataType::Timestamp(None, TimezoneInfo::None),
+                ),
+                (DataType::Time32(TimeUnit::Millisecond), SqlDataType::Time(None, TimezoneInfo::None)),
+                (DataType::Time64(TimeUnit::Microsecond), SqlDataType::Time(None, TimezoneInfo::None)),
+            ]
+            .into_iter()
+            .collect(),
+            cast_transformers: vec![
+                (
+                    (DataType::Float64, DataType::Decimal128(38, 10)),
+                    DecimalCastTransformer::new_dyn(38, 10),
+                ),
+                (
+                    (DataType::Float64, DataType::Decimal256(76, 10)),
+                    DecimalCastTransformer::new_dyn(76, 10),
+                ),
             ]
             .into_iter()
             .collect(),
-            cast_transformers: Default::default(),
             cast_propagates_null: true,
             supports_non_finite_floats: true,
         }
@@ -539,6 +844,19 @@ impl Dialect {
         let mut scalar_transforms: HashMap<String, Arc<dyn FunctionTransformer>> = HashMap::new();
         scalar_transforms.insert("date_add".to_string(), Arc::new(DateAddToIntervalAddition));
 
+        let aggregate_transformers: HashMap<String, Arc<dyn FunctionTransformer>> = vec![(
+            "quantile",
+            PercentileTransformer::new_dyn(
+                PercentileRendering::TrailingArg {
+                    name: "approx_percentile_cont".to_string(),
+                },
+                0.5,
+            ),
+        )]
+        .into_iter()
+        .map(|(name, v)| (name.to_string(), v))
+        .collect();
+
         Self {
             parse_dialect: ParseDialect::DataFusion,
             quote_style: '"',
@@ -650,6 +968,7 @@ impl Dialect {
                 "avg",
                 "sum",
                 "median",
+                "quantile",
                 "var",
                 "var_pop",
                 "stddev",
@@ -678,8 +997,12 @@ impl Dialect {
             .iter()
             .map(|s| s.to_string())
             .collect(),
+            aggregate_functions_as_window: Default::default(),
+            supports_aggregate_window_functions: false,
             scalar_transformers: scalar_transforms,
-            aggregate_transformers: Default::default(),
+            aggregate_transformers,
+            supports_ordered_set_aggregates: false,
+            approx_quantile_transformers: Default::default(),
             values_mode: ValuesMode::ValuesWithSubqueryColumnAliases {
                 explicit_row: false,
             },
@@ -701,6 +1024,14 @@ impl Dialect {
                 (DataType::Float32, SqlDataType::Float(None)),
                 (DataType::Float64, SqlDataType::Double),
                 (DataType::Utf8, SqlDataType::String),
+                (DataType::Date32, SqlDataType::Date),
+                (DataType::Date64, SqlDataType::Date),
+                (
+                    DataType::Timestamp(TimeUnit::Microsecond, None),
+                    SqlDataType::Timestamp(None, TimezoneInfo::None),
+                ),
+                (DataType::Time32(TimeUnit::Millisecond), SqlDataType::Time(None, TimezoneInfo::None)),
+                (DataType::Time64(TimeUnit::Microsecond), SqlDataType::Time(None, TimezoneInfo::None)),
             ]
             .into_iter()
             .collect(),
@@ -771,6 +1102,8 @@ impl Dialect {
             .iter()
             .map(|s| s.to_string())
             .collect(),
+            aggregate_functions_as_window: Default::default(),
+            supports_aggregate_window_functions: false,
             scalar_transformers: vec![
                 ("ln", RenameFunctionTransformer::new_dyn("log")),
                 ("log", RenameFunctionTransformer::new_dyn("log10")),
@@ -782,6 +1115,8 @@ impl Dialect {
             .map(|(name, v)| (name.to_string(), v))
             .collect(),
             aggregate_transformers,
+            supports_ordered_set_aggregates: false,
+            approx_quantile_transformers: Default::default(),
             values_mode: ValuesMode::ValuesWithSubqueryColumnAliases {
                 explicit_row: false,
             },
@@ -803,6 +1138,14 @@ impl Dialect {
                 (DataType::Float32, SqlDataType::Float(None)),
                 (DataType::Float64, SqlDataType::Double),
                 (DataType::Utf8, SqlDataType::Varchar(None)),
+                (DataType::Date32, SqlDataType::Date),
+                (DataType::Date64, SqlDataType::Date),
+                (
+                    DataType::Timestamp(TimeUnit::Microsecond, None),
+                    SqlDataType::Timestamp(None, TimezoneInfo::None),
+                ),
+                (DataType::Time32(TimeUnit::Millisecond), SqlDataType::Time(None, TimezoneInfo::None)),
+                (DataType::Time64(TimeUnit::Microsecond), SqlDataType::Time(None, TimezoneInfo::None)),
             ]
             .into_iter()
             .collect(),
@@ -852,6 +1195,11 @@ impl Dialect {
                 "stddev_pop",
                 "covar_pop",
                 "corr",
+                "bit_and",
+                "bit_or",
+                "bit_xor",
+                "bool_and",
+                "bool_or",
             ]
             .iter()
             .map(|s| s.to_string())
@@ -872,14 +1220,43 @@ impl Dialect {
             .iter()
             .map(|s| s.to_string())
             .collect(),
+            aggregate_functions_as_window: Default::default(),
+            supports_aggregate_window_functions: false,
             scalar_transformers: vec![
                 ("exp", ExpWithPowFunctionTransformer::new_dyn()),
                 ("signum", RenameFunctionTransformer::new_dyn("sign")),
+                ("make_list", RenameFunctionTransformer::new_dyn("list_value")),
+                ("length", RenameFunctionTransformer::new_dyn("len")),
+                ("indexof", RenameFunctionTransformer::new_dyn("list_position")),
+                (
+                    "regexp_match",
+                    RenameFunctionTransformer::new_dyn("regexp_extract"),
+                ),
             ]
             .into_iter()
             .map(|(name, v)| (name.to_string(), v))
             .collect(),
             aggregate_transformers,
+            supports_ordered_set_aggregates: false,
+            approx_quantile_transformers: vec![
+                ("median", 0.5),
+                ("quantile", 0.5),
+                ("q1", 0.25),
+                ("q3", 0.75),
+            ]
+            .into_iter()
+            .map(|(name, default_percentile)| {
+                (
+                    name.to_string(),
+                    PercentileTransformer::new_dyn(
+                        PercentileRendering::TrailingArg {
+                            name: "approx_quantile".to_string(),
+                        },
+                        default_percentile,
+                    ),
+                )
+            })
+            .collect(),
             values_mode: ValuesMode::ValuesWithSubqueryColumnAliases {
                 explicit_row: false,
             },
@@ -897,14 +1274,37 @@ impl Dialect {
                 (DataType::Int32, SqlDataType::Int(None)),
                 (DataType::UInt32, SqlDataType::BigInt(None)),
                 (DataType::Int64, SqlDataType::BigInt(None)),
+                (
+                    DataType::UInt64,
+                    SqlDataType::Custom(ObjectName(vec!["HUGEINT".into()]), Vec::new()),
+                ),
                 (DataType::Float16, SqlDataType::Float(None)),
                 (DataType::Float32, SqlDataType::Float(None)),
                 (DataType::Float64, SqlDataType::Double),
                 (DataType::Utf8, SqlDataType::Varchar(None)),
+                (DataType::Date32, SqlDataType::Date),
+                (DataType::Date64, SqlDataType::Date),
+                (
+                    DataType::Timestamp(TimeUnit::Microsecond, None),
+                    SqlDataType::Timestamp(None, TimezoneInfo::None),
+                ),
+                (DataType::Time32(TimeUnit::Millisecond), SqlDataType::Time(None, TimezoneInfo::None)),
+                (DataType::Time64(TimeUnit::Microsecond), SqlDataType::Time(None, TimezoneInfo::None)),
+            ]
+            .into_iter()
+            .collect(),
+            cast_transformers: vec![
+                (
+                    (DataType::Float64, DataType::Decimal128(38, 10)),
+                    DecimalCastTransformer::new_dyn(38, 10),
+                ),
+                (
+                    (DataType::Float64, DataType::Decimal256(76, 10)),
+                    DecimalCastTransformer::new_dyn(76, 10),
+                ),
             ]
             .into_iter()
             .collect(),
-            cast_transformers: Default::default(),
             cast_propagates_null: true,
             supports_non_finite_floats: true,
         }
@@ -915,6 +1315,11 @@ impl Dialect {
         let aggregate_transformers: HashMap<String, Arc<dyn FunctionTransformer>> = vec![
             ("var", RenameFunctionTransformer::new_dyn("var_samp")),
             ("stddev", RenameFunctionTransformer::new_dyn("stddev_samp")),
+            (
+                "bool_and",
+                BoolAggregateAsMinMaxTransformer::new_dyn(false),
+            ),
+            ("bool_or", BoolAggregateAsMinMaxTransformer::new_dyn(true)),
         ]
         .into_iter()
         .map(|(name, v)| (name.to_string(), v))
@@ -938,10 +1343,23 @@ impl Dialect {
             .iter()
             .map(|s| s.to_string())
             .collect(),
-            aggregate_functions: vec!["min", "max", "count", "avg", "sum", "var_pop", "stddev_pop"]
-                .iter()
-                .map(|s| s.to_string())
-                .collect(),
+            aggregate_functions: vec![
+                "min",
+                "max",
+                "count",
+                "avg",
+                "sum",
+                "var_pop",
+                "stddev_pop",
+                "bit_and",
+                "bit_or",
+                "bit_xor",
+                "bool_and",
+                "bool_or",
+            ]
+            .iter()
+            .map(|s| s.to_string())
+            .collect(),
             window_functions: vec![
                 "row_number",
                 "rank",
@@ -958,6 +1376,8 @@ impl Dialect {
             .iter()
             .map(|s| s.to_string())
             .collect(),
+            aggregate_functions_as_window: Default::default(),
+            supports_aggregate_window_functions: false,
             scalar_transformers: vec![
                 ("log", RenameFunctionTransformer::new_dyn("log10")),
                 ("signum", RenameFunctionTransformer::new_dyn("sign")),
@@ -968,6 +1388,8 @@ impl Dialect {
             .map(|(name, v)| (name.to_string(), v))
             .collect(),
             aggregate_transformers,
+            supports_ordered_set_aggregates: false,
+            approx_quantile_transformers: Default::default(),
             values_mode: ValuesMode::ValuesWithSubqueryColumnAliases { explicit_row: true },
             supports_null_ordering: false,
             impute_fully_qualified: false,
@@ -986,13 +1408,31 @@ impl Dialect {
                 (DataType::Float32, SqlDataType::Float(None)),
                 (DataType::Float64, SqlDataType::Double),
                 (DataType::Utf8, SqlDataType::Char(None)),
+                (DataType::Date32, SqlDataType::Date),
+                (DataType::Date64, SqlDataType::Date),
+                (
+                    DataType::Timestamp(TimeUnit::Microsecond, None),
+                    SqlDataType::Custom(ObjectName(vec!["DATETIME".into()]), Vec::new()),
+                ),
+                (DataType::Time32(TimeUnit::Millisecond), SqlDataType::Time(None, TimezoneInfo::None)),
+                (DataType::Time64(TimeUnit::Microsecond), SqlDataType::Time(None, TimezoneInfo::None)),
             ]
             .into_iter()
             .collect(),
-            cast_transformers: vec![(
-                (DataType::Boolean, DataType::Utf8),
-                BoolToStringWithCase::new_dyn(),
-            )]
+            cast_transformers: vec![
+                (
+                    (DataType::Boolean, DataType::Utf8),
+                    BoolToStringWithCase::new_dyn(),
+                ),
+                (
+                    (DataType::Float64, DataType::Decimal128(38, 10)),
+                    DecimalCastTransformer::new_dyn(38, 10),
+                ),
+                (
+                    (DataType::Float64, DataType::Decimal256(76, 10)),
+                    DecimalCastTransformer::new_dyn(76, 10),
+                ),
+            ]
             .into_iter()
             .collect(),
             cast_propagates_null: true,
@@ -1006,6 +1446,43 @@ impl Dialect {
             ("var", RenameFunctionTransformer::new_dyn("var_samp")),
             ("stddev", RenameFunctionTransformer::new_dyn("stddev_samp")),
             ("covar", RenameFunctionTransformer::new_dyn("covar_samp")),
+            (
+                "median",
+                PercentileTransformer::new_dyn(
+                    PercentileRendering::WithinGroup {
+                        name: "percentile_cont".to_string(),
+                    },
+                    0.5,
+                ),
+            ),
+            (
+                "quantile",
+                PercentileTransformer::new_dyn(
+                    PercentileRendering::WithinGroup {
+                        name: "percentile_cont".to_string(),
+                    },
+                    0.5,
+                ),
+            ),
+            (
+                "q1",
+                PercentileTransformer::new_dyn(
+                    PercentileRendering::WithinGroup {
+                        name: "percentile_cont".to_string(),
+                    },
+                    0.25,
+                ),
+            ),
+            (
+                "q3",
+                PercentileTransformer::new_dyn(
+                    PercentileRendering::WithinGroup {
+                        name: "percentile_cont".to_string(),
+                    },
+                    0.75,
+                ),
+            ),
+            ("mode", ModeAggregateTransformer::new_dyn()),
         ]
         .into_iter()
         .map(|(name, v)| (name.to_string(), v))
@@ -1032,10 +1509,20 @@ impl Dialect {
                 "count",
                 "avg",
                 "sum",
+                "median",
+                "quantile",
+                "q1",
+                "q3",
+                "mode",
                 "var_pop",
                 "stddev_pop",
                 "covar_pop",
                 "corr",
+                "bit_and",
+                "bit_or",
+                "bit_xor",
+                "bool_and",
+                "bool_or",
             ]
             .iter()
             .map(|s| s.to_string())
@@ -1056,6 +1543,11 @@ impl Dialect {
             .iter()
             .map(|s| s.to_string())
             .collect(),
+            aggregate_functions_as_window: vec!["sum", "avg", "min", "max", "count"]
+                .iter()
+                .map(|s| s.to_string())
+                .collect(),
+            supports_aggregate_window_functions: true,
             scalar_transformers: vec![
                 ("log10", LogBaseTransformer::new_dyn(10, true)),
                 ("log2", LogBaseTransformer::new_dyn(2, true)),
@@ -1065,6 +1557,8 @@ impl Dialect {
             .map(|(name, v)| (name.to_string(), v))
             .collect(),
             aggregate_transformers,
+            supports_ordered_set_aggregates: true,
+            approx_quantile_transformers: Default::default(),
             values_mode: ValuesMode::ValuesWithSubqueryColumnAliases {
                 explicit_row: false,
             },
@@ -1086,10 +1580,33 @@ impl Dialect {
                 (DataType::Float32, SqlDataType::Real),
                 (DataType::Float64, SqlDataType::DoublePrecision),
                 (DataType::Utf8, SqlDataType::Text),
+                (DataType::Date32, SqlDataType::Date),
+                (DataType::Date64, SqlDataType::Date),
+                (
+                    DataType::Timestamp(TimeUnit::Microsecond, None),
+                    SqlDataType::Timestamp(None, TimezoneInfo::None),
+                ),
+                (DataType::Time32(TimeUnit::Millisecond), SqlDataType::Time(None, TimezoneInfo::None)),
+                (DataType::Time64(TimeUnit::Microsecond), SqlDataType::Time(None, TimezoneInfo::None)),
+            ]
+            .into_iter()
+            .collect(),
+            cast_transformers: vec![
+                (
+                    (DataType::Float64, DataType::Decimal128(38, 10)),
+                    DecimalCastTransformer::new_dyn(38, 10),
+                ),
+                (
+                    (DataType::Float64, DataType::Decimal256(76, 10)),
+                    DecimalCastTransformer::new_dyn(76, 10),
+                ),
+                (
+                    (DataType::Int64, DataType::Timestamp(TimeUnit::Microsecond, None)),
+                    EpochToTimestampTransformer::new_dyn(|e| format!("to_timestamp({e})")),
+                ),
             ]
             .into_iter()
             .collect(),
-            cast_transformers: Default::default(),
             cast_propagates_null: true,
             supports_non_finite_floats: true,
         }
@@ -1100,6 +1617,34 @@ impl Dialect {
         let aggregate_transformers: HashMap<String, Arc<dyn FunctionTransformer>> = vec![
             ("var", RenameFunctionTransformer::new_dyn("var_samp")),
             ("stddev", RenameFunctionTransformer::new_dyn("stddev_samp")),
+            (
+                "quantile",
+                PercentileTransformer::new_dyn(
+                    PercentileRendering::WithinGroup {
+                        name: "percentile_cont".to_string(),
+                    },
+                    0.5,
+                ),
+            ),
+            (
+                "q1",
+                PercentileTransformer::new_dyn(
+                    PercentileRendering::WithinGroup {
+                        name: "percentile_cont".to_string(),
+                    },
+                    0.25,
+                ),
+            ),
+            (
+                "q3",
+                PercentileTransformer::new_dyn(
+                    PercentileRendering::WithinGroup {
+                        name: "percentile_cont".to_string(),
+                    },
+                    0.75,
+                ),
+            ),
+            ("mode", ModeAggregateTransformer::new_dyn()),
         ]
         .into_iter()
         .map(|(name, v)| (name.to_string(), v))
@@ -1129,8 +1674,18 @@ impl Dialect {
                 "sum",
                 // Median is kind of supported, but usage results in error:
                 // "One or more of the used functions must be applied on at least one user created tables"
+                // `quantile`/`q1`/`q3`, compiled via PERCENTILE_CONT(p) WITHIN GROUP, aren't affected.
+                "quantile",
+                "q1",
+                "q3",
+                "mode",
                 "var_pop",
                 "stddev_pop",
+                "bit_and",
+                "bit_or",
+                "bit_xor",
+                "bool_and",
+                "bool_or",
             ]
             .iter()
             .map(|s| s.to_string())
@@ -1151,6 +1706,8 @@ impl Dialect {
             .iter()
             .map(|s| s.to_string())
             .collect(),
+            aggregate_functions_as_window: Default::default(),
+            supports_aggregate_window_functions: false,
             scalar_transformers: vec![
                 (
                     "log2",
@@ -1174,6 +1731,26 @@ impl Dialect {
             .map(|(name, v)| (name.to_string(), v))
             .collect(),
             aggregate_transformers,
+            supports_ordered_set_aggregates: true,
+            approx_quantile_transformers: vec![
+                ("median", 0.5),
+                ("quantile", 0.5),
+                ("q1", 0.25),
+                ("q3", 0.75),
+            ]
+            .into_iter()
+            .map(|(name, default_percentile)| {
+                (
+                    name.to_string(),
+                    PercentileTransformer::new_dyn(
+                        PercentileRendering::ApproximateWithinGroup {
+                            name: "percentile_disc".to_string(),
+                        },
+                        default_percentile,
+                    ),
+                )
+            })
+            .collect(),
             values_mode: ValuesMode::SelectUnion,
             supports_null_ordering: true,
             impute_fully_qualified: false,
@@ -1193,13 +1770,31 @@ impl Dialect {
                 (DataType::Float32, SqlDataType::Real),
                 (DataType::Float64, SqlDataType::DoublePrecision),
                 (DataType::Utf8, SqlDataType::Text),
+                (DataType::Date32, SqlDataType::Date),
+                (DataType::Date64, SqlDataType::Date),
+                (
+                    DataType::Timestamp(TimeUnit::Microsecond, None),
+                    SqlDataType::Timestamp(None, TimezoneInfo::None),
+                ),
+                (DataType::Time32(TimeUnit::Millisecond), SqlDataType::Time(None, TimezoneInfo::None)),
+                (DataType::Time64(TimeUnit::Microsecond), SqlDataType::Time(None, TimezoneInfo::None)),
             ]
             .into_iter()
             .collect(),
-            cast_transformers: vec![(
-                (DataType::Boolean, DataType::Utf8),
-                BoolToStringWithCase::new_dyn(),
-            )]
+            cast_transformers: vec![
+                (
+                    (DataType::Boolean, DataType::Utf8),
+                    BoolToStringWithCase::new_dyn(),
+                ),
+                (
+                    (DataType::Float64, DataType::Decimal128(38, 10)),
+                    DecimalCastTransformer::new_dyn(38, 10),
+                ),
+                (
+                    (DataType::Float64, DataType::Decimal256(76, 10)),
+                    DecimalCastTransformer::new_dyn(76, 10),
+                ),
+            ]
             .into_iter()
             .collect(),
             cast_propagates_null: false,
@@ -1213,6 +1808,39 @@ impl Dialect {
             ("var", RenameFunctionTransformer::new_dyn("var_samp")),
             ("stddev", RenameFunctionTransformer::new_dyn("stddev_samp")),
             ("covar", RenameFunctionTransformer::new_dyn("covar_samp")),
+            // Snowflake has no native `quantile` function, but supports the same
+            // `PERCENTILE_CONT(p) WITHIN GROUP` syntax postgres/redshift use for it, so route it
+            // through the same exact (non-approximate) transformer they do. Unlike `quantile`,
+            // `median` needs no transformer here since it's already a native Snowflake aggregate
+            // (see `aggregate_functions` below).
+            (
+                "quantile",
+                PercentileTransformer::new_dyn(
+                    PercentileRendering::WithinGroup {
+                        name: "percentile_cont".to_string(),
+                    },
+                    0.5,
+                ),
+            ),
+            (
+                "q1",
+                PercentileTransformer::new_dyn(
+                    PercentileRendering::WithinGroup {
+                        name: "percentile_cont".to_string(),
+                    },
+                    0.25,
+                ),
+            ),
+            (
+                "q3",
+                PercentileTransformer::new_dyn(
+                    PercentileRendering::WithinGroup {
+                        name: "percentile_cont".to_string(),
+                    },
+                    0.75,
+                ),
+            ),
+            ("mode", ModeAggregateTransformer::new_dyn()),
         ]
         .into_iter()
         .map(|(name, v)| (name.to_string(), v))
@@ -1241,10 +1869,18 @@ impl Dialect {
                 "avg",
                 "sum",
                 "median",
+                "q1",
+                "q3",
+                "mode",
                 "var_pop",
                 "stddev_pop",
                 "covar_pop",
                 "corr",
+                "bit_and",
+                "bit_or",
+                "bit_xor",
+                "bool_and",
+                "bool_or",
             ]
             .iter()
             .map(|s| s.to_string())
@@ -1265,6 +1901,8 @@ impl Dialect {
             .iter()
             .map(|s| s.to_string())
             .collect(),
+            aggregate_functions_as_window: Default::default(),
+            supports_aggregate_window_functions: false,
             scalar_transformers: vec![
                 ("log", LogBaseTransformer::new_dyn(10, true)),
                 ("log10", LogBaseTransformer::new_dyn(10, true)),
@@ -1275,6 +1913,26 @@ impl Dialect {
             .map(|(name, v)| (name.to_string(), v))
             .collect(),
             aggregate_transformers,
+            supports_ordered_set_aggregates: true,
+            approx_quantile_transformers: vec![
+                ("median", 0.5),
+                ("quantile", 0.5),
+                ("q1", 0.25),
+                ("q3", 0.75),
+            ]
+            .into_iter()
+            .map(|(name, default_percentile)| {
+                (
+                    name.to_string(),
+                    PercentileTransformer::new_dyn(
+                        PercentileRendering::TrailingArg {
+                            name: "approx_percentile".to_string(),
+                        },
+                        default_percentile,
+                    ),
+                )
+            })
+            .collect(),
             values_mode: ValuesMode::ValuesWithSelectColumnAliases {
                 explicit_row: false,
                 column_prefix: "COLUMN".to_string(),
@@ -1298,10 +1956,29 @@ impl Dialect {
                 (DataType::Float32, SqlDataType::Float(None)),
                 (DataType::Float64, SqlDataType::Double),
                 (DataType::Utf8, SqlDataType::Varchar(None)),
+                (DataType::Date32, SqlDataType::Date),
+                (DataType::Date64, SqlDataType::Date),
+                (
+                    DataType::Timestamp(TimeUnit::Microsecond, None),
+                    SqlDataType::Custom(ObjectName(vec!["TIMESTAMP_NTZ".into()]), Vec::new()),
+                ),
+                (DataType::Time32(TimeUnit::Millisecond), SqlDataType::Time(None, TimezoneInfo::None)),
+                (DataType::Time64(TimeUnit::Microsecond), SqlDataType::Time(None, TimezoneInfo::None)),
+            ]
+            .into_iter()
+            .collect(),
+            cast_transformers: vec![
+                (
+                    (DataType::Float64, DataType::Decimal128(38, 10)),
+                    DecimalCastTransformer::new_dyn(38, 10),
+                ),
+                (
+                    (DataType::Float64, DataType::Decimal256(76, 10)),
+                    DecimalCastTransformer::new_dyn(76, 10),
+                ),
             ]
             .into_iter()
             .collect(),
-            cast_transformers: Default::default(),
             cast_propagates_null: true,
             supports_non_finite_floats: true,
         }
@@ -1309,6 +1986,15 @@ impl Dialect {
 
     pub fn sqlite() -> Self {
         use Operator::*;
+        let aggregate_transformers: HashMap<String, Arc<dyn FunctionTransformer>> = vec![
+            ("var", MomentVarianceTransformer::new_dyn(true, false)),
+            ("var_pop", MomentVarianceTransformer::new_dyn(false, false)),
+            ("stddev", MomentVarianceTransformer::new_dyn(true, true)),
+            ("stddev_pop", MomentVarianceTransformer::new_dyn(false, true)),
+        ]
+        .into_iter()
+        .map(|(name, v)| (name.to_string(), v))
+        .collect();
         Self {
             parse_dialect: ParseDialect::SqLite,
             quote_style: '"',
@@ -1327,10 +2013,20 @@ impl Dialect {
             .iter()
             .map(|s| s.to_string())
             .collect(),
-            aggregate_functions: vec!["min", "max", "count", "avg", "sum"]
-                .iter()
-                .map(|s| s.to_string())
-                .collect(),
+            aggregate_functions: vec![
+                "min",
+                "max",
+                "count",
+                "avg",
+                "sum",
+                "var",
+                "var_pop",
+                "stddev",
+                "stddev_pop",
+            ]
+            .iter()
+            .map(|s| s.to_string())
+            .collect(),
             window_functions: vec![
                 "row_number",
                 "rank",
@@ -1347,8 +2043,12 @@ impl Dialect {
             .iter()
             .map(|s| s.to_string())
             .collect(),
+            aggregate_functions_as_window: Default::default(),
+            supports_aggregate_window_functions: false,
             scalar_transformers: Default::default(),
-            aggregate_transformers: Default::default(),
+            aggregate_transformers,
+            supports_ordered_set_aggregates: false,
+            approx_quantile_transformers: Default::default(),
             values_mode: ValuesMode::ValuesWithSelectColumnAliases {
                 explicit_row: false,
                 column_prefix: "column".to_string(),
@@ -1372,13 +2072,28 @@ impl Dialect {
                 (DataType::Float32, SqlDataType::Real),
                 (DataType::Float64, SqlDataType::Real),
                 (DataType::Utf8, SqlDataType::Text),
+                (DataType::Date32, SqlDataType::Text),
+                (DataType::Date64, SqlDataType::Text),
+                (DataType::Timestamp(TimeUnit::Microsecond, None), SqlDataType::Text),
+                (DataType::Time32(TimeUnit::Millisecond), SqlDataType::Time(None, TimezoneInfo::None)),
+                (DataType::Time64(TimeUnit::Microsecond), SqlDataType::Time(None, TimezoneInfo::None)),
             ]
             .into_iter()
             .collect(),
-            cast_transformers: vec![(
-                (DataType::Boolean, DataType::Utf8),
-                BoolToStringWithCase::new_dyn(),
-            )]
+            cast_transformers: vec![
+                (
+                    (DataType::Boolean, DataType::Utf8),
+                    BoolToStringWithCase::new_dyn(),
+                ),
+                (
+                    (DataType::Utf8, DataType::Timestamp(TimeUnit::Microsecond, None)),
+                    StringToTimestampTransformer::new_dyn(|e| format!("datetime({e})")),
+                ),
+                (
+                    (DataType::Int64, DataType::Timestamp(TimeUnit::Microsecond, None)),
+                    EpochToTimestampTransformer::new_dyn(|e| format!("datetime({e}, 'unixepoch')")),
+                ),
+            ]
             .into_iter()
             .collect(),
             cast_propagates_null: true,
@@ -1387,11 +2102,41 @@ impl Dialect {
     }
 }
 
+/// Dialects registered at runtime via `Dialect::register`, keyed by lowercased name. Consulted by
+/// `FromStr` after the built-in presets, so a name that shadows a preset (e.g. a customized fork
+/// of `"postgres"`) wins, letting callers override a preset as well as add new ones.
+fn custom_dialect_registry() -> &'static Mutex<HashMap<String, Dialect>> {
+    static REGISTRY: OnceLock<Mutex<HashMap<String, Dialect>>> = OnceLock::new();
+    REGISTRY.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+impl Dialect {
+    /// Register `dialect` under `name` (case-insensitive) so that `Dialect::from_str(name)`
+    /// resolves to it from then on. This is how a downstream crate teaches VegaFusion to emit SQL
+    /// for an engine with no built-in preset (Trino, Vertica, ...), or tweaks one of the presets
+    /// above without forking this crate: build a `Dialect` with `from_parts` and the `with_*`
+    /// builders, then register it under the name specs will ask for.
+    pub fn register(name: impl Into<String>, dialect: Dialect) {
+        custom_dialect_registry()
+            .lock()
+            .expect("custom dialect registry lock was poisoned")
+            .insert(name.into().to_ascii_lowercase(), dialect);
+    }
+}
+
 impl FromStr for Dialect {
     type Err = VegaFusionError;
 
     fn from_str(s: &str) -> Result<Self> {
-        Ok(match s.to_ascii_lowercase().as_str() {
+        let lowercased = s.to_ascii_lowercase();
+        if let Some(dialect) = custom_dialect_registry()
+            .lock()
+            .expect("custom dialect registry lock was poisoned")
+            .get(&lowercased)
+        {
+            return Ok(dialect.clone());
+        }
+        Ok(match lowercased.as_str() {
             "athena" => Dialect::athena(),
             "bigquery" => Dialect::bigquery(),
             "clickhouse" => Dialect::clickhouse(),
@@ -1563,6 +2308,187 @@ impl FunctionTransformer for CastArgsFunctionTransformer {
     }
 }
 
+/// How a dialect expects the percentile argument of a `median`/`quantile` aggregate to be
+/// rendered, since each backend spells "percentile of col" differently.
+#[derive(Clone, Debug)]
+enum PercentileRendering {
+    /// `<name>(col, p)`, e.g. DataFusion's `approx_percentile_cont(col, p)`
+    TrailingArg { name: String },
+    /// ClickHouse-style parametric aggregate: `<name>(p)(col)`
+    ParametricCall { name: String },
+    /// ANSI ordered-set aggregate: `<name>(p) WITHIN GROUP (ORDER BY col)`
+    WithinGroup { name: String },
+    /// Redshift's approximate ordered-set aggregate: `APPROXIMATE <name>(p) WITHIN GROUP (ORDER BY col)`
+    ApproximateWithinGroup { name: String },
+    /// BigQuery: `APPROX_QUANTILES(col, 100)[OFFSET(round(p * 100))]`
+    BigQueryApproxQuantiles,
+}
+
+/// Compiles `median(col)` / `q1(col)` / `q3(col)` / `quantile(col, p)` to each dialect's native
+/// percentile syntax. When `p` is given as the second argument it is used; otherwise the
+/// transformer's `default_percentile` is used (0.5 for `median`/`quantile`, 0.25 for `q1`, 0.75
+/// for `q3`, as registered by each dialect).
+#[derive(Clone, Debug)]
+struct PercentileTransformer {
+    rendering: PercentileRendering,
+    default_percentile: f64,
+}
+impl PercentileTransformer {
+    pub fn new_dyn(rendering: PercentileRendering, default_percentile: f64) -> Arc<dyn FunctionTransformer> {
+        Arc::new(Self {
+            rendering,
+            default_percentile,
+        })
+    }
+
+    fn percentile_arg(args: &[Expr]) -> Result<f64> {
+        match &args[1] {
+            Expr::Literal(ScalarValue::Float64(Some(p))) => Ok(*p),
+            Expr::Literal(ScalarValue::Float32(Some(p))) => Ok(*p as f64),
+            Expr::Literal(ScalarValue::Int64(Some(p))) => Ok(*p as f64),
+            _ => Err(VegaFusionError::sql_not_supported(
+                "quantile's percentile argument must be a numeric literal",
+            )),
+        }
+    }
+}
+impl FunctionTransformer for PercentileTransformer {
+    fn transform(&self, args: &[Expr], dialect: &Dialect, schema: &DFSchema) -> Result<SqlExpr> {
+        let p = if args.len() > 1 {
+            Self::percentile_arg(args)?
+        } else {
+            self.default_percentile
+        };
+        let col = args[0].to_sql(dialect, schema)?;
+
+        match &self.rendering {
+            PercentileRendering::TrailingArg { name } => Ok(SqlExpr::Function(Function {
+                name: ObjectName(vec![Ident {
+                    value: name.clone(),
+                    quote_style: None,
+                }]),
+                args: vec![
+                    FunctionArg::Unnamed(FunctionArgExpr::Expr(col)),
+                    FunctionArg::Unnamed(FunctionArgExpr::Expr(SqlExpr::Value(SqlValue::Number(
+                        p.to_string(),
+                        false,
+                    )))),
+                ],
+                over: None,
+                distinct: false,
+                special: false,
+            })),
+            PercentileRendering::ParametricCall { name } => Ok(SqlExpr::Function(Function {
+                name: ObjectName(vec![Ident {
+                    value: format!("{name}({p})"),
+                    quote_style: None,
+                }]),
+                args: vec![FunctionArg::Unnamed(FunctionArgExpr::Expr(col))],
+                over: None,
+                distinct: false,
+                special: false,
+            })),
+            PercentileRendering::WithinGroup { name } => Ok(SqlExpr::Identifier(Ident {
+                value: format!("{name}({p}) WITHIN GROUP (ORDER BY {col})"),
+                quote_style: None,
+            })),
+            PercentileRendering::ApproximateWithinGroup { name } => Ok(SqlExpr::Identifier(Ident {
+                value: format!("APPROXIMATE {name}({p}) WITHIN GROUP (ORDER BY {col})"),
+                quote_style: None,
+            })),
+            PercentileRendering::BigQueryApproxQuantiles => Ok(SqlExpr::Identifier(Ident {
+                value: format!(
+                    "APPROX_QUANTILES({col}, 100)[OFFSET({})]",
+                    (p * 100.0).round() as i64
+                ),
+                quote_style: None,
+            })),
+        }
+    }
+}
+
+/// Compiles `mode(col)` to the SQL-standard ordered-set aggregate `MODE() WITHIN GROUP (ORDER BY
+/// col)`, for dialects with `supports_ordered_set_aggregates`.
+#[derive(Clone, Debug)]
+struct ModeAggregateTransformer;
+impl ModeAggregateTransformer {
+    pub fn new_dyn() -> Arc<dyn FunctionTransformer> {
+        Arc::new(Self)
+    }
+}
+impl FunctionTransformer for ModeAggregateTransformer {
+    fn transform(&self, args: &[Expr], dialect: &Dialect, schema: &DFSchema) -> Result<SqlExpr> {
+        let col = args[0].to_sql(dialect, schema)?;
+        Ok(SqlExpr::Identifier(Ident {
+            value: format!("MODE() WITHIN GROUP (ORDER BY {col})"),
+            quote_style: None,
+        }))
+    }
+}
+
+/// MySQL has no `BOOL_AND`/`BOOL_OR` aggregate, so rewrite `bool_and(x)` to `MIN(x) = 1` and
+/// `bool_or(x)` to `MAX(x) = 1` over a column that's already 0/1 (MySQL has no distinct boolean
+/// storage type; booleans are a `TINYINT(1)` alias).
+#[derive(Clone, Debug)]
+struct BoolAggregateAsMinMaxTransformer {
+    use_max: bool,
+}
+impl BoolAggregateAsMinMaxTransformer {
+    pub fn new_dyn(use_max: bool) -> Arc<dyn FunctionTransformer> {
+        Arc::new(Self { use_max })
+    }
+}
+impl FunctionTransformer for BoolAggregateAsMinMaxTransformer {
+    fn transform(&self, args: &[Expr], dialect: &Dialect, schema: &DFSchema) -> Result<SqlExpr> {
+        let col = args[0].to_sql(dialect, schema)?;
+        let name = if self.use_max { "MAX" } else { "MIN" };
+        Ok(SqlExpr::Identifier(Ident {
+            value: format!("{name}({col}) = 1"),
+            quote_style: None,
+        }))
+    }
+}
+
+/// Emulates `var`/`var_pop`/`stddev`/`stddev_pop` for dialects with no native variance aggregate,
+/// via the algebraic moment identity: population variance is
+/// `(sum(x*x) - sum(x)*sum(x)/count(x)) / count(x)`, and sample variance divides that same
+/// numerator by `count(x) - 1` instead, guarded to NULL (rather than dividing by zero) when
+/// `count(x) = 1`. `sqrt` wraps the result for the standard-deviation variants. Built entirely
+/// from `sum`/`count`, so NULL inputs are ignored the same way a native aggregate would.
+#[derive(Clone, Debug)]
+struct MomentVarianceTransformer {
+    sample: bool,
+    sqrt: bool,
+}
+impl MomentVarianceTransformer {
+    pub fn new_dyn(sample: bool, sqrt: bool) -> Arc<dyn FunctionTransformer> {
+        Arc::new(Self { sample, sqrt })
+    }
+}
+impl FunctionTransformer for MomentVarianceTransformer {
+    fn transform(&self, args: &[Expr], dialect: &Dialect, schema: &DFSchema) -> Result<SqlExpr> {
+        let col = args[0].to_sql(dialect, schema)?;
+        let numerator =
+            format!("(sum(({col}) * ({col})) - sum({col}) * sum({col}) / count({col}))");
+        let variance = if self.sample {
+            format!(
+                "(CASE WHEN count({col}) - 1 = 0 THEN NULL ELSE {numerator} / (count({col}) - 1) END)"
+            )
+        } else {
+            format!("({numerator} / count({col}))")
+        };
+        let value = if self.sqrt {
+            format!("sqrt({variance})")
+        } else {
+            variance
+        };
+        Ok(SqlExpr::Identifier(Ident {
+            value,
+            quote_style: None,
+        }))
+    }
+}
+
 #[derive(Clone, Debug)]
 struct LogBaseTransformer {
     pub base: i32,
@@ -1719,9 +2645,418 @@ impl BoolToStringWithCase {
 }
 impl CastTransformer for BoolToStringWithCase {
     fn transform(&self, arg: &Expr, dialect: &Dialect, schema: &DFSchema) -> Result<SqlExpr> {
-        when(arg.clone().eq(lit(true)), lit("true"))
-            .when(arg.clone().eq(lit(false)), lit("false"))
-            .otherwise(lit(ScalarValue::Null))?
-            .to_sql(dialect, schema)
+        let case = when(arg.clone().eq(lit(true)), lit("true"))
+            .when(arg.clone().eq(lit(false)), lit("false"));
+        let cased = if arg.nullable(schema).unwrap_or(true) {
+            case.otherwise(lit(ScalarValue::Null))?
+        } else {
+            // `arg` can never be NULL here, so the trailing `ELSE NULL` branch a nullable source
+            // would need is dead weight -- leave it off and let the two-way CASE speak for itself.
+            case.end()?
+        };
+        cased.to_sql(dialect, schema)
+    }
+}
+
+/// Casts `arg` to a `DECIMAL(precision, scale)` SQL type. `cast_datatypes` is keyed by exact
+/// `DataType` equality, so it can't express "any precision/scale" for `DataType::Decimal128`.
+/// This transformer works around that: if `arg` already resolves to a `Decimal128(p, s)` in
+/// `schema`, its precision/scale are reused (a decimal-to-decimal cast is a no-op on
+/// precision/scale in Vega); otherwise it falls back to the precision/scale it was registered
+/// with.
+#[derive(Debug)]
+pub struct DecimalCastTransformer {
+    fallback_precision: u8,
+    fallback_scale: i8,
+}
+impl DecimalCastTransformer {
+    pub fn new_dyn(fallback_precision: u8, fallback_scale: i8) -> Arc<dyn CastTransformer> {
+        Arc::new(Self {
+            fallback_precision,
+            fallback_scale,
+        })
+    }
+}
+impl CastTransformer for DecimalCastTransformer {
+    fn transform(&self, arg: &Expr, dialect: &Dialect, schema: &DFSchema) -> Result<SqlExpr> {
+        let (precision, scale) = match arg.get_type(schema) {
+            Ok(DataType::Decimal128(precision, scale)) => (precision, scale),
+            Ok(DataType::Decimal256(precision, scale)) => (precision, scale),
+            _ => (self.fallback_precision, self.fallback_scale),
+        };
+        let arg_sql = arg.to_sql(dialect, schema)?;
+        let cast_sql = SqlExpr::Cast {
+            expr: Box::new(arg_sql.clone()),
+            data_type: SqlDataType::Decimal(Some(precision as u64), Some(scale.max(0) as u64)),
+        };
+        // Only dialects that don't propagate NULL through CAST need the guard at all, and even
+        // then only when `arg` can actually be NULL -- a non-nullable source can skip it entirely.
+        if dialect.cast_propagates_null || !arg.nullable(schema).unwrap_or(true) {
+            Ok(cast_sql)
+        } else {
+            // This dialect doesn't propagate NULL through CAST on its own, so make it explicit
+            // rather than risk a dialect-specific zero/error on a NULL decimal source value.
+            Ok(SqlExpr::Identifier(Ident {
+                value: format!("CASE WHEN {arg_sql} IS NULL THEN NULL ELSE {cast_sql} END"),
+                quote_style: None,
+            }))
+        }
+    }
+}
+
+/// Parses a string timestamp via dialect-specific syntax, since `CAST(text AS TIMESTAMP)` isn't
+/// portable: SQLite has no real temporal type and needs `datetime(...)` to normalize the text
+/// into its canonical ISO-8601 storage form, while Postgres's native `::timestamp` cast already
+/// handles this and is used as-is.
+#[derive(Clone, Debug)]
+struct StringToTimestampTransformer {
+    render: fn(&SqlExpr) -> String,
+}
+impl StringToTimestampTransformer {
+    pub fn new_dyn(render: fn(&SqlExpr) -> String) -> Arc<dyn CastTransformer> {
+        Arc::new(Self { render })
+    }
+}
+impl CastTransformer for StringToTimestampTransformer {
+    fn transform(&self, arg: &Expr, dialect: &Dialect, schema: &DFSchema) -> Result<SqlExpr> {
+        let arg_sql = arg.to_sql(dialect, schema)?;
+        Ok(SqlExpr::Identifier(Ident {
+            value: (self.render)(&arg_sql),
+            quote_style: None,
+        }))
+    }
+}
+
+/// Converts a Unix epoch (seconds, as an integer column) to a dialect-native timestamp, e.g.
+/// Postgres's `to_timestamp(...)` or SQLite's `datetime(..., 'unixepoch')`.
+#[derive(Clone, Debug)]
+struct EpochToTimestampTransformer {
+    render: fn(&SqlExpr) -> String,
+}
+impl EpochToTimestampTransformer {
+    pub fn new_dyn(render: fn(&SqlExpr) -> String) -> Arc<dyn CastTransformer> {
+        Arc::new(Self { render })
+    }
+}
+impl CastTransformer for EpochToTimestampTransformer {
+    fn transform(&self, arg: &Expr, dialect: &Dialect, schema: &DFSchema) -> Result<SqlExpr> {
+        let arg_sql = arg.to_sql(dialect, schema)?;
+        Ok(SqlExpr::Identifier(Ident {
+            value: (self.render)(&arg_sql),
+            quote_style: None,
+        }))
+    }
+}
+
+// Reverse direction: sqlparser Expr -> DataFusion Expr
+
+/// Inverse of `ToSqlExpr`. Parses a `sqlparser::ast::Expr` (produced by parsing SQL with
+/// `dialect.parser_dialect()`) back into a DataFusion `Expr`, so that user-supplied SQL filter/
+/// calculate snippets can be compiled, and so pushdown can be validated by round-tripping the SQL
+/// the crate emits. Constructs this can't invert return a `VegaFusionError` rather than being
+/// silently dropped.
+pub trait FromSqlExpr: Sized {
+    fn from_sql(sql_expr: &SqlExpr, dialect: &Dialect, schema: &DFSchema) -> Result<Self>;
+}
+
+impl FromSqlExpr for Expr {
+    fn from_sql(sql_expr: &SqlExpr, dialect: &Dialect, schema: &DFSchema) -> Result<Expr> {
+        match sql_expr {
+            SqlExpr::Identifier(ident) => column_expr(&[ident.clone()], schema),
+            SqlExpr::CompoundIdentifier(idents) => column_expr(idents, schema),
+            SqlExpr::Nested(inner) => Expr::from_sql(inner, dialect, schema),
+            SqlExpr::Value(value) => sql_value_to_scalar(value).map(Expr::Literal),
+            SqlExpr::UnaryOp { op, expr } => {
+                let expr = Expr::from_sql(expr, dialect, schema)?;
+                match op {
+                    SqlUnaryOperator::Minus => Ok(Expr::Negative(Box::new(expr))),
+                    SqlUnaryOperator::Not => Ok(Expr::Not(Box::new(expr))),
+                    _ => Err(VegaFusionError::sql_not_supported(format!(
+                        "Unsupported unary operator in SQL expression: {op:?}"
+                    ))),
+                }
+            }
+            SqlExpr::BinaryOp { left, op, right } => {
+                let op = sql_binary_op_to_op(op, dialect)?;
+                Ok(Expr::BinaryExpr(expr::BinaryExpr {
+                    left: Box::new(Expr::from_sql(left, dialect, schema)?),
+                    op,
+                    right: Box::new(Expr::from_sql(right, dialect, schema)?),
+                }))
+            }
+            SqlExpr::Cast { expr, data_type } => {
+                let expr = Expr::from_sql(expr, dialect, schema)?;
+                let data_type = sql_data_type_to_data_type(data_type, dialect)?;
+                Ok(Expr::Cast(expr::Cast {
+                    expr: Box::new(expr),
+                    data_type,
+                }))
+            }
+            SqlExpr::Function(function) => {
+                let name = function
+                    .name
+                    .0
+                    .last()
+                    .map(|ident| ident.value.to_ascii_lowercase())
+                    .unwrap_or_default();
+
+                // Only plain, unrenamed pass-through names can be inverted unambiguously: several
+                // distinct DataFusion functions may be registered under the same dialect-native
+                // name by `scalar_transformers`/`aggregate_transformers`, so there's no reliable
+                // name to invert back to for those. This also means a `compile_unregistered_scalar_call`
+                // fallback call - whose name is, by definition, in neither allow-list - can't be
+                // round-tripped through this path either: the dialect has no record that an
+                // unrecognized rendered name (e.g. a `transformer`'s dialect-native output) is
+                // actually a safe, unrenamed pass-through rather than an ambiguous rename target,
+                // so both are conservatively rejected alike.
+                if !dialect.scalar_functions.contains(&name) && !dialect.aggregate_functions.contains(&name)
+                {
+                    return Err(VegaFusionError::sql_not_supported(format!(
+                        "Cannot parse call to SQL function '{name}' back into a DataFusion expression"
+                    )));
+                }
+
+                let args = function
+                    .args
+                    .iter()
+                    .map(|arg| match arg {
+                        FunctionArg::Unnamed(FunctionArgExpr::Expr(arg_expr)) => {
+                            Expr::from_sql(arg_expr, dialect, schema)
+                        }
+                        _ => Err(VegaFusionError::sql_not_supported(
+                            "Only unnamed expression function arguments are supported",
+                        )),
+                    })
+                    .collect::<Result<Vec<_>>>()?;
+
+                Ok(Expr::ScalarFunction(expr::ScalarFunction {
+                    func_def: ScalarFunctionDefinition::Name(Arc::new(name)),
+                    args,
+                }))
+            }
+            _ => Err(VegaFusionError::sql_not_supported(format!(
+                "Unsupported SQL expression: {sql_expr}"
+            ))),
+        }
+    }
+}
+
+/// Resolve a (possibly dot-qualified) SQL identifier into a `Column` expr, validating that the
+/// (unqualified) name actually exists in `schema`.
+fn column_expr(idents: &[Ident], schema: &DFSchema) -> Result<Expr> {
+    let name = idents.last().expect("identifier has no parts").value.clone();
+    if schema.field_with_unqualified_name(&name).is_err() {
+        return Err(VegaFusionError::sql_not_supported(format!(
+            "Column '{name}' not found in schema"
+        )));
+    }
+    let relation = if idents.len() > 1 {
+        Some(
+            idents[..idents.len() - 1]
+                .iter()
+                .map(|ident| ident.value.as_str())
+                .collect::<Vec<_>>()
+                .join(".")
+                .into(),
+        )
+    } else {
+        None
+    };
+    Ok(Expr::Column(datafusion_common::Column { relation, name }))
+}
+
+fn sql_value_to_scalar(value: &SqlValue) -> Result<ScalarValue> {
+    match value {
+        SqlValue::Boolean(b) => Ok(ScalarValue::Boolean(Some(*b))),
+        SqlValue::Null => Ok(ScalarValue::Null),
+        SqlValue::SingleQuotedString(s) | SqlValue::DoubleQuotedString(s) => {
+            Ok(ScalarValue::Utf8(Some(s.clone())))
+        }
+        SqlValue::Number(n, _) => {
+            if let Ok(i) = n.parse::<i64>() {
+                Ok(ScalarValue::Int64(Some(i)))
+            } else if let Ok(f) = n.parse::<f64>() {
+                Ok(ScalarValue::Float64(Some(f)))
+            } else {
+                Err(VegaFusionError::sql_not_supported(format!(
+                    "Invalid numeric SQL literal: {n}"
+                )))
+            }
+        }
+        _ => Err(VegaFusionError::sql_not_supported(format!(
+            "Unsupported SQL literal: {value}"
+        ))),
+    }
+}
+
+/// Map a `sqlparser` binary operator back to a DataFusion `Operator`, requiring the operator to
+/// be one `dialect.binary_ops` actually declares support for.
+fn sql_binary_op_to_op(op: &SqlBinaryOperator, dialect: &Dialect) -> Result<Operator> {
+    use Operator::*;
+    let op = match op {
+        SqlBinaryOperator::Eq => Eq,
+        SqlBinaryOperator::NotEq => NotEq,
+        SqlBinaryOperator::Lt => Lt,
+        SqlBinaryOperator::LtEq => LtEq,
+        SqlBinaryOperator::Gt => Gt,
+        SqlBinaryOperator::GtEq => GtEq,
+        SqlBinaryOperator::Plus => Plus,
+        SqlBinaryOperator::Minus => Minus,
+        SqlBinaryOperator::Multiply => Multiply,
+        SqlBinaryOperator::Divide => Divide,
+        SqlBinaryOperator::Modulo => Modulo,
+        SqlBinaryOperator::And => And,
+        SqlBinaryOperator::Or => Or,
+        _ => {
+            return Err(VegaFusionError::sql_not_supported(format!(
+                "Unsupported SQL binary operator: {op:?}"
+            )))
+        }
+    };
+    if !dialect.binary_ops.contains(&op) {
+        return Err(VegaFusionError::sql_not_supported(format!(
+            "SQL binary operator {op:?} is not supported by this dialect"
+        )));
+    }
+    Ok(op)
+}
+
+/// Invert the (non-exhaustive) `cast_datatypes` mapping: find a `DataType` that this dialect
+/// renders as `sql_type`. Several Arrow `DataType`s can map to the same SQL type (e.g. every
+/// signed integer width may render as `INT`), so this recovers an equivalent, not necessarily the
+/// original, `DataType`.
+fn sql_data_type_to_data_type(sql_type: &SqlDataType, dialect: &Dialect) -> Result<DataType> {
+    dialect
+        .cast_datatypes
+        .iter()
+        .find(|(_, sql)| *sql == *sql_type)
+        .map(|(data_type, _)| data_type.clone())
+        .ok_or_else(|| {
+            VegaFusionError::sql_not_supported(format!(
+                "Cannot parse SQL type {sql_type} back into an Arrow DataType"
+            ))
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arrow::datatypes::{Field, Schema};
+    use datafusion_expr::col;
+
+    fn schema_with_column_a() -> DFSchema {
+        DFSchema::try_from(Schema::new(vec![Field::new("a", DataType::Float64, true)])).unwrap()
+    }
+
+    /// An approximate `q1` must render with the 25th percentile, not silently fall back to the
+    /// approximate transformer's 0.5 default meant for `median`.
+    #[test]
+    fn approx_q1_uses_the_25th_percentile_not_the_median() {
+        let dialect = Dialect::duckdb();
+        let schema = DFSchema::empty();
+        let args = vec![col("a")];
+
+        let median_sql = dialect
+            .compile_quantile_aggregate("median", &args, &schema, true)
+            .unwrap()
+            .to_string();
+        let q1_sql = dialect
+            .compile_quantile_aggregate("q1", &args, &schema, true)
+            .unwrap()
+            .to_string();
+        let q3_sql = dialect
+            .compile_quantile_aggregate("q3", &args, &schema, true)
+            .unwrap()
+            .to_string();
+
+        assert_ne!(q1_sql, median_sql);
+        assert_ne!(q3_sql, median_sql);
+        assert!(q1_sql.contains("0.25"));
+        assert!(q3_sql.contains("0.75"));
+        assert!(median_sql.contains("0.5"));
+    }
+
+    // `compile_aggregate_as_window_call` must actually consult `supports_as_window_function`
+    // rather than always emitting `agg(...) OVER (...)` regardless of dialect support.
+    #[test]
+    fn compile_aggregate_as_window_call_rejects_unsupported_dialects() {
+        let schema = DFSchema::empty();
+        let args = vec![col("a")];
+
+        // bigquery supports sum/avg/min/max/count as window functions
+        let bigquery_sql = Dialect::bigquery()
+            .compile_aggregate_as_window_call("sum", &args, &schema)
+            .unwrap()
+            .to_string();
+        assert!(bigquery_sql.contains("sum"));
+
+        // athena does not register any aggregate_functions_as_window entries
+        let err = Dialect::athena()
+            .compile_aggregate_as_window_call("sum", &args, &schema)
+            .unwrap_err();
+        assert!(err.to_string().contains("window function"));
+    }
+
+    // A registered pass-through scalar function (one actually in `scalar_functions`) is the one
+    // case `compile_unregistered_scalar_call`'s sibling, plain registered calls, can round trip -
+    // confirms `Expr::from_sql` actually exercises compiled SQL output, not just `Dialect` fields.
+    #[test]
+    fn registered_scalar_call_round_trips_through_from_sql() {
+        let dialect = Dialect::datafusion();
+        let schema = schema_with_column_a();
+        let args = vec![col("a")];
+
+        let compiled = dialect
+            .compile_unregistered_scalar_call("abs", &args, &schema)
+            .unwrap();
+        assert_eq!(compiled.to_string(), "abs(a)");
+
+        let round_tripped = Expr::from_sql(&compiled, &dialect, &schema).unwrap();
+        match round_tripped {
+            Expr::ScalarFunction(expr::ScalarFunction { func_def, args }) => {
+                assert_eq!(
+                    func_def,
+                    ScalarFunctionDefinition::Name(Arc::new("abs".to_string()))
+                );
+                assert_eq!(args.len(), 1);
+            }
+            other => panic!("expected Expr::ScalarFunction, got {other:?}"),
+        }
+    }
+
+    // Documents the known, deliberate limitation called out on `compile_unregistered_scalar_call`:
+    // its output can't be round-tripped, since a name absent from both allow-lists is
+    // indistinguishable from an ambiguous `scalar_transformers`/`aggregate_transformers` rename
+    // target once it's just a string embedded in SQL text.
+    #[test]
+    fn unregistered_scalar_call_output_does_not_round_trip() {
+        let dialect = Dialect::datafusion();
+        let schema = schema_with_column_a();
+        let args = vec![col("a")];
+
+        let compiled = dialect
+            .compile_unregistered_scalar_call("totally_unregistered_fn", &args, &schema)
+            .unwrap();
+        assert_eq!(compiled.to_string(), "totally_unregistered_fn(a)");
+
+        assert!(Expr::from_sql(&compiled, &dialect, &schema).is_err());
+    }
+
+    // Snowflake has no native `quantile` function, but supports the same `PERCENTILE_CONT(p)
+    // WITHIN GROUP` syntax postgres/redshift already use for it - this must actually compile
+    // rather than falling through to an unrenamed, invalid `quantile(...)` call.
+    #[test]
+    fn snowflake_quantile_compiles_via_percentile_cont_within_group() {
+        let dialect = Dialect::snowflake();
+        let schema = schema_with_column_a();
+        let args = vec![col("a"), datafusion_expr::lit(0.9_f64)];
+
+        let sql = dialect
+            .compile_quantile_aggregate("quantile", &args, &schema, false)
+            .unwrap()
+            .to_string();
+
+        assert!(sql.contains("percentile_cont(0.9)"));
+        assert!(sql.contains("WITHIN GROUP"));
     }
 }